@@ -0,0 +1,166 @@
+//! A recent blockhash shared by all sending tasks: a background task refreshes it on a
+//! timer or on new slots, and senders read the current value without an RPC round trip.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::hash::Hash;
+use tokio::{sync::watch, time};
+
+/// When the cache requests a new blockhash.
+pub enum Refresh {
+    /// Once per interval.
+    Interval(Duration),
+    /// On new slots, e.g. from a Geyser subscription, but at most once per `min_interval`:
+    /// slots that arrive in the meantime result in a single request. Refreshing stops
+    /// once the slot sender is dropped.
+    Slots { slots: watch::Receiver<u64>, min_interval: Duration },
+}
+
+impl Refresh {
+    /// Waits until the next request is due; `last` is when the previous one was made.
+    /// Returns `false` when there is nothing left to refresh on.
+    async fn wait(&mut self, last: time::Instant) -> bool {
+        match self {
+            Refresh::Interval(interval) => {
+                time::sleep_until(last + *interval).await;
+                true
+            }
+            Refresh::Slots { slots, min_interval } => {
+                time::sleep_until(last + *min_interval).await;
+                slots.changed().await.is_ok()
+            }
+        }
+    }
+}
+
+/// A cached blockhash together with its validity window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    /// Last block height at which a transaction using this blockhash can still land.
+    pub last_valid_block_height: u64,
+    /// When the cache first saw this blockhash.
+    pub fetched_at: Instant,
+    /// When the node last answered a blockhash request.
+    pub refreshed_at: Instant,
+    /// Consecutive failed refreshes; a successful response resets the counter.
+    pub failed_refreshes: u32,
+}
+
+impl CachedBlockhash {
+    fn new(blockhash: Hash, last_valid_block_height: u64, now: Instant) -> Self {
+        Self { blockhash, last_valid_block_height, fetched_at: now, refreshed_at: now, failed_refreshes: 0 }
+    }
+
+    /// How old the blockhash is: time since the cache received it.
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+
+    /// Time since the last successful refresh; keeps growing while requests fail.
+    pub fn staleness(&self) -> Duration {
+        self.refreshed_at.elapsed()
+    }
+
+    /// Records a node response. A repeated blockhash keeps its `fetched_at`, otherwise
+    /// its age would never grow.
+    fn update(&mut self, blockhash: Hash, last_valid_block_height: u64, now: Instant) {
+        if blockhash != self.blockhash {
+            *self = Self::new(blockhash, last_valid_block_height, now);
+        }
+        self.refreshed_at = now;
+        self.failed_refreshes = 0;
+    }
+}
+
+/// The latest blockhash, kept up to date by a background task. Clones share one value;
+/// the task exits once the last clone is dropped.
+#[derive(Clone)]
+pub struct BlockhashCache(watch::Receiver<CachedBlockhash>);
+
+impl BlockhashCache {
+    /// Fetches the first blockhash at the client's commitment and starts refreshing.
+    pub async fn spawn(client: Arc<RpcClient>, refresh: Refresh) -> Result<Self, ClientError> {
+        let (blockhash, last_valid_block_height) =
+            client.get_latest_blockhash_with_commitment(client.commitment()).await?;
+        let (publish, current) = watch::channel(CachedBlockhash::new(blockhash, last_valid_block_height, Instant::now()));
+        tokio::spawn(async move {
+            let mut refresh = refresh;
+            let mut last = time::Instant::now();
+            loop {
+                if !refresh.wait(last).await || publish.is_closed() {
+                    return;
+                }
+                last = time::Instant::now();
+                // The previous blockhash stays in use until the next successful response;
+                // failures show up in `failed_refreshes` and `staleness`.
+                match client.get_latest_blockhash_with_commitment(client.commitment()).await {
+                    Ok((blockhash, last_valid)) => {
+                        publish.send_modify(|cached| cached.update(blockhash, last_valid, Instant::now()))
+                    }
+                    Err(e) => publish.send_modify(|cached| {
+                        cached.failed_refreshes += 1;
+                        eprintln!("Failed to refresh blockhash ({} in a row): {}", cached.failed_refreshes, e);
+                    }),
+                }
+            }
+        });
+        Ok(Self(current))
+    }
+
+    /// The current cached blockhash.
+    pub fn get(&self) -> CachedBlockhash {
+        *self.0.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_blockhash_keeps_its_age() {
+        let start = Instant::now();
+        let first = Hash::new_unique();
+        let mut cached = CachedBlockhash::new(first, 150, start);
+        cached.failed_refreshes = 2;
+
+        let repeat = start + Duration::from_millis(400);
+        cached.update(first, 151, repeat);
+        assert_eq!((cached.fetched_at, cached.refreshed_at), (start, repeat));
+        assert_eq!(cached.last_valid_block_height, 150);
+        assert_eq!(cached.failed_refreshes, 0);
+
+        let second = Hash::new_unique();
+        let later = start + Duration::from_millis(800);
+        cached.update(second, 152, later);
+        assert_eq!(cached, CachedBlockhash::new(second, 152, later));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slot_refresh_waits_for_min_interval() {
+        let (slots, updates) = watch::channel(0);
+        let min_interval = Duration::from_millis(500);
+        let mut refresh = Refresh::Slots { slots: updates, min_interval };
+
+        let last = time::Instant::now();
+        for slot in 1..=3 {
+            slots.send_replace(slot);
+        }
+        assert!(refresh.wait(last).await);
+        assert_eq!(time::Instant::now() - last, min_interval);
+
+        // Without a new slot no request is made, even after the interval.
+        let last = time::Instant::now();
+        assert!(time::timeout(Duration::from_secs(2), refresh.wait(last)).await.is_err());
+        slots.send_replace(4);
+        assert!(refresh.wait(last).await);
+
+        drop(slots);
+        assert!(!refresh.wait(time::Instant::now()).await);
+    }
+}
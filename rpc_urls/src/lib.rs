@@ -0,0 +1,27 @@
+//! Solana node URLs shared by all tasks, so every binary derives them the same way.
+
+/// Derives the PubSub URL from an RPC URL the way the Solana CLI does: http(s) becomes
+/// ws(s) and the default local RPC port 8899 becomes 8900.
+pub fn websocket_url(rpc_url: &str) -> String {
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    };
+    url.replacen(":8899", ":8900", 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_url_follows_cli_conventions() {
+        assert_eq!(websocket_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(websocket_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("http://127.0.0.1:8899/rpc:8899"), "ws://127.0.0.1:8900/rpc:8899");
+        assert_eq!(websocket_url("ws://127.0.0.1:8900"), "ws://127.0.0.1:8900");
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::reqwest;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{scanner::WalletReport, wallets::resolve_wallet};
+
+/// Exit status used when at least one wallet crossed a threshold.
+pub const ALERT_EXIT_CODE: i32 = 3;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub struct Thresholds {
+    pub min_lamports: Option<u64>,
+    pub max_lamports: Option<u64>,
+}
+
+impl Thresholds {
+    /// Per-wallet values win over the global ones, field by field.
+    fn or(self, fallback: Thresholds) -> Thresholds {
+        Thresholds {
+            min_lamports: self.min_lamports.or(fallback.min_lamports),
+            max_lamports: self.max_lamports.or(fallback.max_lamports),
+        }
+    }
+}
+
+/// `alerts` section of the task1 config.
+#[derive(Debug, Default, Deserialize)]
+pub struct AlertConfig {
+    #[serde(flatten)]
+    pub global: Thresholds,
+    /// Overrides keyed by wallet pubkey or label.
+    #[serde(default)]
+    pub wallets: HashMap<String, Thresholds>,
+    /// Receives a JSON `POST` with every alert of a run.
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Breach {
+    BelowMin,
+    AboveMax,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename = "alert")]
+pub struct Alert {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub wallet: Pubkey,
+    pub breach: Breach,
+    pub lamports: u64,
+    pub threshold: u64,
+    pub slot: u64,
+}
+
+fn serialize_pubkey<S: serde::Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(pubkey)
+}
+
+impl AlertConfig {
+    /// Resolves the override keys like `public_keys` entries, through `labels`. A key that
+    /// names none of the `known` wallets is a config error, since it would never apply.
+    pub fn overrides(
+        &self,
+        labels: &HashMap<String, String>,
+        known: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, Thresholds>, String> {
+        let known: HashSet<&Pubkey> = known.iter().collect();
+        self.wallets
+            .iter()
+            .map(|(key, thresholds)| {
+                let wallet = resolve_wallet(key, labels)
+                    .map_err(|e| format!("invalid wallet `{}` in alerts.wallets: {}", key, e))?;
+                if !known.contains(&wallet) {
+                    return Err(format!("unknown wallet `{}` in alerts.wallets", key));
+                }
+                Ok((wallet, thresholds.or(self.global)))
+            })
+            .collect()
+    }
+
+    /// Checks every successfully fetched balance; duplicated wallets alert once.
+    pub fn evaluate(&self, overrides: &HashMap<Pubkey, Thresholds>, reports: &[WalletReport]) -> Vec<Alert> {
+        let mut seen = HashSet::new();
+        reports
+            .iter()
+            .filter(|r| seen.insert(r.wallet))
+            .filter_map(|report| {
+                let balance = report.result.as_ref().ok()?;
+                let thresholds = overrides.get(&report.wallet).copied().unwrap_or(self.global);
+                let (breach, threshold) = match thresholds {
+                    Thresholds { min_lamports: Some(min), .. } if balance.lamports < min => (Breach::BelowMin, min),
+                    Thresholds { max_lamports: Some(max), .. } if balance.lamports > max => (Breach::AboveMax, max),
+                    _ => return None,
+                };
+                Some(Alert {
+                    wallet: report.wallet,
+                    breach,
+                    lamports: balance.lamports,
+                    threshold,
+                    slot: balance.slot,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    alerts: &'a [Alert],
+}
+
+pub async fn send_webhook(url: &str, alerts: &[Alert]) -> Result<(), reqwest::Error> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?
+        .post(url)
+        .json(&WebhookPayload { alerts })
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Balance;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn report(wallet: Pubkey, lamports: u64) -> WalletReport {
+        WalletReport {
+            wallet,
+            result: Ok(Balance { lamports, exists: true, slot: 9 }),
+            tokens: None,
+            mismatch: None,
+        }
+    }
+
+    #[test]
+    fn per_wallet_thresholds_override_global() {
+        let (fee_payer, treasury, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let config = AlertConfig {
+            global: Thresholds { min_lamports: Some(100), max_lamports: None },
+            wallets: HashMap::from([
+                (fee_payer.to_string(), Thresholds { min_lamports: Some(1_000), max_lamports: None }),
+                ("treasury".to_string(), Thresholds { min_lamports: None, max_lamports: Some(5_000) }),
+            ]),
+            webhook_url: None,
+        };
+        let labels = HashMap::from([("treasury".to_string(), treasury.to_string())]);
+
+        let overrides = config.overrides(&labels, &[fee_payer, treasury, other]).unwrap();
+        let alerts = config.evaluate(&overrides, &[report(fee_payer, 500), report(treasury, 6_000), report(other, 200)]);
+
+        assert_eq!(
+            alerts,
+            vec![
+                Alert { wallet: fee_payer, breach: Breach::BelowMin, lamports: 500, threshold: 1_000, slot: 9 },
+                Alert { wallet: treasury, breach: Breach::AboveMax, lamports: 6_000, threshold: 5_000, slot: 9 },
+            ]
+        );
+    }
+
+    #[test]
+    fn override_keys_must_name_known_wallets() {
+        let (known, stranger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let config = |key: String| AlertConfig {
+            wallets: HashMap::from([(key, Thresholds { min_lamports: Some(1), max_lamports: None })]),
+            ..AlertConfig::default()
+        };
+        let labels = HashMap::from([("vault".to_string(), stranger.to_string())]);
+
+        let error = config(stranger.to_string()).overrides(&labels, &[known]).unwrap_err();
+        assert_eq!(error, format!("unknown wallet `{}` in alerts.wallets", stranger));
+        assert!(config("vault".to_string()).overrides(&labels, &[known]).unwrap_err().starts_with("unknown wallet `vault`"));
+        assert!(config("payroll".to_string())
+            .overrides(&labels, &[known])
+            .unwrap_err()
+            .starts_with("invalid wallet `payroll` in alerts.wallets: not valid base58"));
+        assert!(config("vault".to_string()).overrides(&labels, &[known, stranger]).unwrap().contains_key(&stranger));
+    }
+
+    #[tokio::test]
+    async fn webhook_posts_alerts_as_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            // Read the headers, then exactly `Content-Length` bytes of body.
+            let complete = |request: &[u8]| {
+                let text = String::from_utf8_lossy(request);
+                let (headers, body) = text.split_once("\r\n\r\n")?;
+                let length: usize = headers.lines().find_map(|line| {
+                    let line = line.to_ascii_lowercase();
+                    line.strip_prefix("content-length:")?.trim().parse().ok()
+                })?;
+                (body.len() >= length).then_some(())
+            };
+            while complete(&request).is_none() {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed before the full request arrived");
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let wallet = Pubkey::new_unique();
+        let alert = Alert { wallet, breach: Breach::BelowMin, lamports: 1, threshold: 2, slot: 3 };
+        send_webhook(&url, &[alert]).await.unwrap();
+
+        let request = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert!(request.starts_with("POST /hook"));
+        let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["alerts"][0]["type"], "alert");
+        assert_eq!(body["alerts"][0]["wallet"], wallet.to_string());
+        assert_eq!(body["alerts"][0]["breach"], "below_min");
+    }
+}
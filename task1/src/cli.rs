@@ -0,0 +1,262 @@
+use std::{error::Error, io::BufRead, path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    output::OutputFormat,
+    pool::{EndpointSpec, Strategy},
+    retry::RetryPolicy,
+    wallets,
+};
+
+/// Scans SOL balances for a list of wallets.
+#[derive(Debug, Parser)]
+#[command(name = "task1", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Wallet pubkeys to scan; use `-` to read them from stdin (one per line).
+    /// When omitted, `public_keys` from the config file is used.
+    pub pubkeys: Vec<String>,
+
+    /// Path to the YAML config with `public_keys`.
+    #[arg(short, long, default_value = "task1/config.yaml")]
+    pub config: PathBuf,
+
+    /// RPC URL or cluster shorthand: mainnet (m), devnet (d), testnet (t), localnet (l).
+    /// Repeat to spread requests over several endpoints; append `#WEIGHT` for `--strategy weighted`.
+    #[arg(short = 'u', long = "url", default_value = "mainnet")]
+    pub rpc_urls: Vec<String>,
+
+    /// How requests are distributed across multiple `--url` endpoints.
+    #[arg(long, value_enum, default_value_t = Strategy::RoundRobin)]
+    pub strategy: Strategy,
+
+    /// Fetch every balance from the first two endpoints and flag disagreements.
+    #[arg(long, conflicts_with = "watch")]
+    pub cross_check: bool,
+
+    /// Maximum number of RPC requests in flight.
+    #[arg(long, default_value_t = 5)]
+    pub concurrency: usize,
+
+    /// Attempts per RPC request (including the first) when it is throttled, fails with 5xx or times out.
+    #[arg(long, default_value_t = 5)]
+    pub max_attempts: u32,
+
+    /// Base delay in milliseconds for exponential backoff between attempts.
+    #[arg(long, default_value_t = 250)]
+    pub retry_base_ms: u64,
+
+    /// Random jitter added to each backoff, as a fraction of the delay (0.0-1.0).
+    #[arg(long, default_value_t = 0.5)]
+    pub retry_jitter: f64,
+
+    /// Commitment level used for every RPC query.
+    #[arg(long, value_enum, default_value_t = Commitment::Confirmed)]
+    pub commitment: Commitment,
+
+    /// Output format for the balance report.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Also list SPL Token and Token-2022 accounts owned by each wallet.
+    #[arg(long)]
+    pub tokens: bool,
+
+    /// Keep running and print every lamport balance change via websocket subscriptions.
+    #[arg(long, conflicts_with = "tokens")]
+    pub watch: bool,
+
+    /// PubSub websocket URL for `--watch`; derived from the RPC URL when omitted.
+    #[arg(long)]
+    pub ws_url: Option<String>,
+
+    /// Record this scan into a SQLite snapshot database.
+    #[arg(long, value_name = "DB", num_args = 0..=1, default_missing_value = DEFAULT_SNAPSHOT_DB, conflicts_with = "watch")]
+    pub snapshot: Option<PathBuf>,
+}
+
+pub const DEFAULT_SNAPSHOT_DB: &str = "task1/snapshots.db";
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List wallets whose balance changed, appeared or disappeared between two snapshots.
+    Diff {
+        /// Snapshot database written by `--snapshot`.
+        #[arg(long, default_value = DEFAULT_SNAPSHOT_DB)]
+        db: PathBuf,
+
+        /// Older snapshot id; defaults to the second most recent snapshot.
+        #[arg(requires = "to")]
+        from: Option<i64>,
+
+        /// Newer snapshot id; defaults to the most recent snapshot.
+        to: Option<i64>,
+
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// List recorded snapshots.
+    Snapshots {
+        #[arg(long, default_value = DEFAULT_SNAPSHOT_DB)]
+        db: PathBuf,
+
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl From<Commitment> for CommitmentConfig {
+    fn from(commitment: Commitment) -> Self {
+        match commitment {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+/// Expands cluster shorthands to their public RPC endpoints; anything else is used verbatim.
+pub fn resolve_rpc_url(url: &str) -> String {
+    match url {
+        "mainnet" | "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "localnet" | "localhost" | "l" => "http://127.0.0.1:8899",
+        other => other,
+    }
+    .to_string()
+}
+
+impl Cli {
+    pub fn endpoints(&self) -> Result<Vec<EndpointSpec>, Box<dyn Error>> {
+        let endpoints = self.rpc_urls
+            .iter()
+            .map(|spec| EndpointSpec::parse(spec, resolve_rpc_url))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.cross_check && endpoints.len() < 2 {
+            return Err("--cross-check needs at least two --url endpoints".into());
+        }
+        Ok(endpoints)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.max(1),
+            base_delay: Duration::from_millis(self.retry_base_ms),
+            max_delay: Duration::from_secs(30),
+            jitter: self.retry_jitter,
+        }
+    }
+
+    /// Returns the pubkeys passed on the command line (expanding `-` from stdin),
+    /// or `None` when the config file should be used instead.
+    pub fn read_pubkeys(&self) -> Result<Option<Vec<Pubkey>>, Box<dyn Error>> {
+        if self.pubkeys.is_empty() {
+            return Ok(None);
+        }
+
+        let mut wallets = Vec::new();
+        for arg in &self.pubkeys {
+            if arg == "-" {
+                wallets.extend(read_pubkey_lines(std::io::stdin().lock())?);
+            } else {
+                wallets.push(parse_pubkey(arg)?);
+            }
+        }
+        Ok(Some(wallets))
+    }
+}
+
+/// Reads one pubkey per line, skipping blank lines.
+fn read_pubkey_lines(reader: impl BufRead) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    let mut wallets = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            wallets.push(parse_pubkey(line)?);
+        }
+    }
+    Ok(wallets)
+}
+
+fn parse_pubkey(s: &str) -> Result<Pubkey, Box<dyn Error>> {
+    wallets::check_pubkey(s).map_err(|e| format!("invalid pubkey `{}`: {}", s, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::BufReader};
+
+    use super::*;
+
+    #[test]
+    fn cluster_shorthands_expand_and_custom_urls_pass_through() {
+        for (shorthand, url) in [
+            ("mainnet", "https://api.mainnet-beta.solana.com"),
+            ("m", "https://api.mainnet-beta.solana.com"),
+            ("devnet", "https://api.devnet.solana.com"),
+            ("d", "https://api.devnet.solana.com"),
+            ("testnet", "https://api.testnet.solana.com"),
+            ("t", "https://api.testnet.solana.com"),
+            ("localhost", "http://127.0.0.1:8899"),
+            ("l", "http://127.0.0.1:8899"),
+        ] {
+            assert_eq!(resolve_rpc_url(shorthand), url, "{}", shorthand);
+        }
+        assert_eq!(resolve_rpc_url("https://rpc.example.com:8899"), "https://rpc.example.com:8899");
+    }
+
+    #[test]
+    fn invalid_pubkeys_are_rejected() {
+        let cli = Cli::try_parse_from(["task1", "not-a-pubkey"]).unwrap();
+        let error = cli.read_pubkeys().unwrap_err().to_string();
+        assert!(error.contains("invalid pubkey `not-a-pubkey`"), "{}", error);
+    }
+
+    #[test]
+    fn pubkeys_are_read_from_a_file_skipping_blank_lines() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let path = std::env::temp_dir().join(format!("task1-pubkeys-{}.txt", std::process::id()));
+        fs::write(&path, format!("{}\n\n   \n  {}  \n", first, second)).unwrap();
+        let wallets = read_pubkey_lines(BufReader::new(fs::File::open(&path).unwrap()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(wallets.unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn flags_parse_into_cli() {
+        let pubkey = Pubkey::new_unique().to_string();
+        let cli = Cli::try_parse_from([
+            "task1", &pubkey, "-u", "devnet", "-u", "http://127.0.0.1:8899#3", "--concurrency", "8",
+            "--commitment", "finalized", "-o", "json", "--tokens",
+        ])
+        .unwrap();
+        assert_eq!(cli.read_pubkeys().unwrap(), Some(vec![pubkey.parse().unwrap()]));
+        assert_eq!(cli.rpc_urls, ["devnet", "http://127.0.0.1:8899#3"]);
+        assert_eq!(cli.concurrency, 8);
+        assert!(matches!(cli.commitment, Commitment::Finalized));
+        assert!(matches!(cli.output, OutputFormat::Json));
+        assert!(cli.tokens);
+
+        let defaults = Cli::try_parse_from(["task1"]).unwrap();
+        assert_eq!(defaults.read_pubkeys().unwrap(), None);
+        assert_eq!(defaults.rpc_urls, ["mainnet"]);
+        assert!(matches!(defaults.commitment, Commitment::Confirmed));
+
+        assert!(Cli::try_parse_from(["task1", "--commitment", "recent"]).is_err());
+        assert!(Cli::try_parse_from(["task1", "--tokens", "--watch"]).is_err());
+        assert!(Cli::try_parse_from(["task1", "--cross-check", "--watch"]).is_err());
+    }
+}
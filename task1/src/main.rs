@@ -1,50 +1,194 @@
-use std::{error::Error, sync::Arc};
-use futures::stream::{FuturesUnordered, StreamExt};
+mod alerts;
+mod cli;
+mod output;
+mod pool;
+mod retry;
+mod scanner;
+mod snapshot;
+mod tokens;
+mod wallets;
+mod watch;
+
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    sync::Arc,
+};
+use clap::Parser;
 use serde::Deserialize;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
-use tokio::sync::Semaphore;
+
+use cli::{Cli, Command};
 
 #[derive(Debug, Deserialize)]
 struct PukeysConfig {
     pub public_keys: Vec<String>,
+    /// Mint allowlist for `--tokens`; empty means every mint is reported.
+    #[serde(default)]
+    pub token_mints: Vec<String>,
+    /// Names usable in `public_keys` instead of the pubkey itself.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Balance thresholds checked after every scan.
+    #[serde(default)]
+    pub alerts: Option<alerts::AlertConfig>,
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let config = config::Config::builder()
-        .add_source(config::File::with_name("/home/ando/documents/other/test/task1/config.yaml"))
-        .build()?;
-    let config: PukeysConfig = config.try_deserialize()?;
-
-    let rpc_url = "https://api.mainnet-beta.solana.com";
-    let client = Arc::new(RpcClient::new(rpc_url.to_string()));
-
-    let wallets: Vec<Pubkey> = config.public_keys
-        .iter()
-        .map(|s| Pubkey::from_str_const(s))
-        .collect();
-
-    let semaphore = Arc::new(Semaphore::new(5));
-    let mut tasks = FuturesUnordered::new();
-
-    for wallet in wallets.into_iter() {
-        let client = Arc::clone(&client);
-        let sem = Arc::clone(&semaphore);
-        tasks.push(tokio::spawn(async move {
-            let _permit = sem.acquire_owned().await;
-            let result = client.get_balance(&wallet).await;
-            (wallet, result)
-        }));
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Some(Command::Diff { db, from, to, output }) => {
+            let store = snapshot::SnapshotStore::open(db)?;
+            let (from, to) = match (from, to) {
+                (Some(from), Some(to)) => (*from, *to),
+                _ => store.latest_pair()?.ok_or("need at least two snapshots to diff")?,
+            };
+            for id in [from, to] {
+                if !store.exists(id)? {
+                    return Err(format!("snapshot {} not found in {}", id, db.display()).into());
+                }
+            }
+            output::print_diff(*output, from, to, &store.diff(from, to)?)?;
+            return Ok(());
+        }
+        Some(Command::Snapshots { db, output }) => {
+            let store = snapshot::SnapshotStore::open(db)?;
+            output::print_snapshots(*output, &store.list()?)?;
+            return Ok(());
+        }
+        None => {}
+    }
+
+    let config: Option<PukeysConfig> = if cli.config.exists() {
+        let config = config::Config::builder()
+            .add_source(config::File::from(cli.config.as_path()))
+            .build()?;
+        Some(config.try_deserialize()?)
+    } else {
+        None
+    };
+
+    let wallets: Vec<Pubkey> = match cli.read_pubkeys()? {
+        Some(wallets) => wallets,
+        None => {
+            let config = config.as_ref()
+                .ok_or_else(|| format!("config file {} not found", cli.config.display()))?;
+            let lines = wallets::EntryLines::locate(&std::fs::read_to_string(&cli.config)?);
+            let (wallets, invalid) = wallets::resolve_wallets(&config.public_keys, &config.labels, &lines);
+            for entry in &invalid {
+                match entry.line {
+                    Some(line) => eprintln!("{}:{}: {}", cli.config.display(), line, entry),
+                    None => eprintln!("{}: {}", cli.config.display(), entry),
+                }
+            }
+            if wallets.is_empty() && !invalid.is_empty() {
+                return Err(format!("no valid wallets in {}", cli.config.display()).into());
+            }
+            if !invalid.is_empty() {
+                eprintln!("Skipping {} invalid entries, scanning {} wallets", invalid.len(), wallets.len());
+            }
+            wallets
+        }
+    };
+
+    let token_mints = config.as_ref()
+        .map(|c| {
+            c.token_mints
+                .iter()
+                .map(|mint| {
+                    wallets::check_pubkey(mint.trim())
+                        .map_err(|reason| format!("invalid mint `{}` in token_mints: {}", mint, reason))
+                })
+                .collect::<Result<HashSet<Pubkey>, _>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    // Resolved before the scan so a bad override key fails fast. Overrides may name any
+    // configured wallet, even when `--pubkeys` scans a different set.
+    let alert_overrides = match config.as_ref().and_then(|c| Some((c, c.alerts.as_ref()?))) {
+        Some((config, alert_config)) => {
+            let lines = wallets::EntryLines::default();
+            let (mut known, _) = wallets::resolve_wallets(&config.public_keys, &config.labels, &lines);
+            known.extend_from_slice(&wallets);
+            alert_config.overrides(&config.labels, &known)?
+        }
+        None => HashMap::new(),
+    };
+
+    let endpoints = cli.endpoints()?;
+    let pool = pool::RpcPool::new(
+        &endpoints,
+        cli.strategy,
+        cli.commitment.into(),
+        cli.retry_policy(),
+        cli.concurrency,
+    );
+    let client = Arc::clone(&pool.client);
+
+    if cli.watch {
+        let ws_url = cli.ws_url.clone().unwrap_or_else(|| rpc_urls::websocket_url(&endpoints[0].url));
+        tokio::select! {
+            result = watch::watch_balances(client, &ws_url, &wallets, cli.output, cli.concurrency) => result?,
+            _ = tokio::signal::ctrl_c() => {}
+        }
+        return Ok(());
     }
 
-    while let Some(join_result) = tasks.next().await {
-        match join_result {
-            Ok((wallet, Ok(balance))) => println!("Wallet {}: balance: {} lamports", wallet, balance),
-            Ok((wallet, Err(e))) => eprintln!("Error for wallet {}: {:?}", wallet, e),
-            Err(e) => eprintln!("Task join error: {:?}", e),
+    let mut reports = if cli.cross_check {
+        pool::cross_check(&pool, &wallets, cli.concurrency).await
+    } else {
+        scanner::scan_balances(Arc::clone(&client), &wallets, cli.concurrency).await
+    };
+
+    if cli.tokens {
+        let unique = scanner::dedup_wallets(&wallets);
+        let holdings = tokens::scan_token_holdings(client, &unique, &token_mints, cli.concurrency).await;
+        for report in &mut reports {
+            report.tokens = holdings.get(&report.wallet).cloned();
         }
     }
 
+    output::print_reports(cli.output, &reports)?;
+
+    if let Some(db) = &cli.snapshot {
+        let mut store = snapshot::SnapshotStore::open(db)?;
+        let urls: Vec<&str> = endpoints.iter().map(|e| e.url.as_str()).collect();
+        let id = store.record(&urls.join(","), &reports)?;
+        eprintln!("Saved snapshot {} to {}", id, db.display());
+    }
+
+    let alerts = match config.as_ref().and_then(|c| c.alerts.as_ref()) {
+        Some(alert_config) => {
+            let alerts = alert_config.evaluate(&alert_overrides, &reports);
+            if !alerts.is_empty() {
+                output::print_alerts(cli.output, &alerts)?;
+                if let Some(url) = &alert_config.webhook_url {
+                    if let Err(e) = alerts::send_webhook(url, &alerts).await {
+                        eprintln!("Failed to deliver alerts to webhook {}: {}", url, e);
+                    }
+                }
+            }
+            alerts
+        }
+        None => Vec::new(),
+    };
+
+    for endpoint in &pool.endpoints {
+        if endpoint.limiter.limit() < cli.concurrency {
+            eprintln!(
+                "Scan finished with concurrency on {} throttled down to {}",
+                endpoint.url,
+                endpoint.limiter.limit()
+            );
+        }
+    }
+
+    if !alerts.is_empty() {
+        std::process::exit(alerts::ALERT_EXIT_CODE);
+    }
+
     Ok(())
 }
@@ -0,0 +1,642 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+
+use crate::{
+    alerts::Alert,
+    pool::{Mismatch, ProviderBalance},
+    scanner::WalletReport,
+    snapshot::{BalanceDiff, ChangeKind, SnapshotInfo},
+    tokens::{TokenHolding, TOKEN_2022_PROGRAM_ID},
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned table with SOL and lamports columns.
+    Table,
+    /// One JSON object per line.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+/// Totals over distinct wallets: a wallet listed twice is counted once in every field.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub wallets: usize,
+    pub total_lamports: u64,
+    pub total_sol: String,
+    pub errors: usize,
+    pub missing: usize,
+    pub token_errors: usize,
+    pub mismatches: usize,
+}
+
+impl Summary {
+    pub fn from_reports(reports: &[WalletReport]) -> Self {
+        let mut summary = Summary::default();
+        let mut counted = HashSet::new();
+        for report in reports {
+            if !counted.insert(report.wallet) {
+                continue;
+            }
+            summary.wallets += 1;
+            match &report.result {
+                Ok(balance) => {
+                    if !balance.exists {
+                        summary.missing += 1;
+                    }
+                    summary.total_lamports += balance.lamports;
+                }
+                Err(_) => summary.errors += 1,
+            }
+            if let Some(Err(_)) = &report.tokens {
+                summary.token_errors += 1;
+            }
+            if report.mismatch.is_some() {
+                summary.mismatches += 1;
+            }
+        }
+        summary.total_sol = format_sol(summary.total_lamports);
+        summary
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Balance {
+        wallet: String,
+        lamports: Option<u64>,
+        sol: Option<String>,
+        exists: Option<bool>,
+        slot: Option<u64>,
+        error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mismatch: Option<&'a [ProviderBalance]>,
+    },
+    Token {
+        wallet: String,
+        account: Option<String>,
+        mint: Option<String>,
+        program: Option<&'static str>,
+        amount: Option<u64>,
+        decimals: Option<u8>,
+        ui_amount: Option<String>,
+        error: Option<String>,
+    },
+    Summary(&'a Summary),
+    BalanceChange {
+        wallet: String,
+        slot: u64,
+        previous_lamports: u64,
+        lamports: u64,
+        delta: i128,
+    },
+    Snapshot {
+        id: i64,
+        taken_at: String,
+        slot: Option<u64>,
+        rpc_url: &'a str,
+        wallets: usize,
+    },
+    Diff {
+        from: i64,
+        to: i64,
+        wallet: String,
+        change: ChangeKind,
+        before: Option<u64>,
+        after: Option<u64>,
+        delta: i128,
+    },
+}
+
+/// A lamport balance change observed in watch mode.
+#[derive(Debug, Clone, Copy)]
+pub struct BalanceChange {
+    pub wallet: Pubkey,
+    pub slot: u64,
+    pub previous: u64,
+    pub lamports: u64,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> i128 {
+        self.lamports as i128 - self.previous as i128
+    }
+}
+
+impl<'a> From<&'a WalletReport> for Record<'a> {
+    fn from(report: &'a WalletReport) -> Self {
+        match &report.result {
+            Ok(balance) => Record::Balance {
+                wallet: report.wallet.to_string(),
+                lamports: Some(balance.lamports),
+                sol: Some(format_sol(balance.lamports)),
+                exists: Some(balance.exists),
+                slot: Some(balance.slot),
+                error: None,
+                mismatch: report.mismatch.as_ref().map(|m| m.providers.as_slice()),
+            },
+            Err(e) => Record::Balance {
+                wallet: report.wallet.to_string(),
+                lamports: None,
+                sol: None,
+                exists: None,
+                slot: None,
+                error: Some(e.to_string()),
+                mismatch: None,
+            },
+        }
+    }
+}
+
+/// Expands the token section of a report into `Record::Token` entries.
+fn token_records(report: &WalletReport) -> Vec<Record<'static>> {
+    match &report.tokens {
+        None => Vec::new(),
+        Some(Ok(holdings)) => holdings
+            .iter()
+            .map(|h| Record::Token {
+                wallet: report.wallet.to_string(),
+                account: Some(h.account.to_string()),
+                mint: Some(h.mint.to_string()),
+                program: Some(program_name(h)),
+                amount: Some(h.amount),
+                decimals: Some(h.decimals),
+                ui_amount: Some(format_units(h.amount, h.decimals)),
+                error: None,
+            })
+            .collect(),
+        Some(Err(e)) => vec![Record::Token {
+            wallet: report.wallet.to_string(),
+            account: None,
+            mint: None,
+            program: None,
+            amount: None,
+            decimals: None,
+            ui_amount: None,
+            error: Some(e.to_string()),
+        }],
+    }
+}
+
+fn program_name(holding: &TokenHolding) -> &'static str {
+    if holding.program == TOKEN_2022_PROGRAM_ID {
+        "spl-token-2022"
+    } else {
+        "spl-token"
+    }
+}
+
+/// Formats a raw integer amount with `decimals` fractional digits, without floating point.
+pub fn format_units(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    format!("{}.{}", whole, fraction)
+}
+
+/// Describes a cross-check mismatch as `url=lamports@slot` pairs.
+fn describe_mismatch(mismatch: &Mismatch) -> String {
+    mismatch
+        .providers
+        .iter()
+        .map(|p| format!("{}={}@{}", p.url, p.lamports, p.slot))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format_units(lamports, LAMPORTS_PER_SOL.ilog10() as u8)
+}
+
+pub fn print_reports(format: OutputFormat, reports: &[WalletReport]) -> io::Result<()> {
+    let summary = Summary::from_reports(reports);
+    let mut out = io::stdout().lock();
+    match format {
+        OutputFormat::Table => write_table(&mut out, reports, &summary),
+        OutputFormat::Json => write_json(&mut out, reports, &summary),
+        OutputFormat::Csv => write_csv(&mut out, reports, &summary),
+    }
+}
+
+fn write_table(out: &mut impl Write, reports: &[WalletReport], summary: &Summary) -> io::Result<()> {
+    writeln!(out, "{:<44}  {:>20}  {:>20}  STATUS", "WALLET", "SOL", "LAMPORTS")?;
+    for report in reports {
+        match &report.result {
+            Ok(balance) => writeln!(
+                out,
+                "{:<44}  {:>20}  {:>20}  {}",
+                report.wallet,
+                format_sol(balance.lamports),
+                balance.lamports,
+                match (&report.mismatch, balance.exists) {
+                    (Some(mismatch), _) => format!("MISMATCH {}", describe_mismatch(mismatch)),
+                    (None, true) => "ok".to_string(),
+                    (None, false) => "not found".to_string(),
+                },
+            )?,
+            Err(e) => writeln!(out, "{:<44}  {:>20}  {:>20}  error: {}", report.wallet, "-", "-", e)?,
+        }
+        match &report.tokens {
+            None => {}
+            Some(Ok(holdings)) => {
+                for h in holdings {
+                    writeln!(
+                        out,
+                        "  mint {:<44}  {:>28}  account {} ({})",
+                        h.mint,
+                        format_units(h.amount, h.decimals),
+                        h.account,
+                        program_name(h),
+                    )?;
+                }
+            }
+            Some(Err(e)) => writeln!(out, "  tokens error: {}", e)?,
+        }
+    }
+    writeln!(
+        out,
+        "{:<44}  {:>20}  {:>20}  {} wallets, {} errors, {} not found, {} token errors, {} mismatches",
+        "TOTAL",
+        summary.total_sol,
+        summary.total_lamports,
+        summary.wallets,
+        summary.errors,
+        summary.missing,
+        summary.token_errors,
+        summary.mismatches,
+    )
+}
+
+fn write_json(out: &mut impl Write, reports: &[WalletReport], summary: &Summary) -> io::Result<()> {
+    for report in reports {
+        serde_json::to_writer(&mut *out, &Record::from(report))?;
+        writeln!(out)?;
+        for record in token_records(report) {
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+    }
+    serde_json::to_writer(&mut *out, &Record::Summary(summary))?;
+    writeln!(out)
+}
+
+/// One rectangular table: the `record` column tells wallet balances (`wallet`), token
+/// holdings (`token`) and run totals (`summary`, as `key`/`value` pairs) apart.
+fn write_csv(out: &mut impl Write, reports: &[WalletReport], summary: &Summary) -> io::Result<()> {
+    let mut columns = vec!["record", "wallet", "lamports", "sol", "exists", "slot", "error", "mismatch"];
+    // Token columns are only emitted when token scanning was requested.
+    if reports.iter().any(|r| r.tokens.is_some()) {
+        columns.extend(["token_account", "mint", "token_program", "token_amount", "ui_amount", "decimals"]);
+    }
+    columns.extend(["key", "value"]);
+    let mut row = |fields: &[(&str, String)]| {
+        let line: Vec<&str> = columns
+            .iter()
+            .map(|column| fields.iter().find(|(name, _)| name == column).map_or("", |(_, value)| value.as_str()))
+            .collect();
+        writeln!(out, "{}", line.join(","))
+    };
+    row(&columns.iter().map(|c| (*c, c.to_string())).collect::<Vec<_>>())?;
+
+    for report in reports {
+        let wallet = report.wallet.to_string();
+        match &report.result {
+            Ok(balance) => row(&[
+                ("record", "wallet".to_string()),
+                ("wallet", wallet.clone()),
+                ("lamports", balance.lamports.to_string()),
+                ("sol", format_sol(balance.lamports)),
+                ("exists", balance.exists.to_string()),
+                ("slot", balance.slot.to_string()),
+                ("mismatch", report.mismatch.as_ref().map(|m| csv_field(&describe_mismatch(m))).unwrap_or_default()),
+            ])?,
+            Err(e) => row(&[
+                ("record", "wallet".to_string()),
+                ("wallet", wallet.clone()),
+                ("error", csv_field(&e.to_string())),
+            ])?,
+        }
+        match &report.tokens {
+            None => {}
+            Some(Ok(holdings)) => {
+                for h in holdings {
+                    row(&[
+                        ("record", "token".to_string()),
+                        ("wallet", wallet.clone()),
+                        ("token_account", h.account.to_string()),
+                        ("mint", h.mint.to_string()),
+                        ("token_program", program_name(h).to_string()),
+                        ("token_amount", h.amount.to_string()),
+                        ("ui_amount", format_units(h.amount, h.decimals)),
+                        ("decimals", h.decimals.to_string()),
+                    ])?;
+                }
+            }
+            Some(Err(e)) => row(&[
+                ("record", "token".to_string()),
+                ("wallet", wallet.clone()),
+                ("error", csv_field(&e.to_string())),
+            ])?,
+        }
+    }
+
+    let totals = [
+        ("wallets", summary.wallets.to_string()),
+        ("errors", summary.errors.to_string()),
+        ("not_found", summary.missing.to_string()),
+        ("token_errors", summary.token_errors.to_string()),
+        ("mismatches", summary.mismatches.to_string()),
+        ("total_lamports", summary.total_lamports.to_string()),
+        ("total_sol", summary.total_sol.clone()),
+    ];
+    for (key, value) in totals {
+        row(&[("record", "summary".to_string()), ("key", key.to_string()), ("value", value)])?;
+    }
+    Ok(())
+}
+
+pub fn print_change_header(format: OutputFormat) -> io::Result<()> {
+    if let OutputFormat::Csv = format {
+        println!("wallet,slot,previous_lamports,lamports,delta");
+    }
+    Ok(())
+}
+
+pub fn write_change(out: &mut impl Write, format: OutputFormat, change: &BalanceChange) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => writeln!(
+            out,
+            "slot {}: wallet {} balance {} -> {} lamports ({:+})",
+            change.slot,
+            change.wallet,
+            change.previous,
+            change.lamports,
+            change.delta(),
+        ),
+        OutputFormat::Json => {
+            let record = Record::BalanceChange {
+                wallet: change.wallet.to_string(),
+                slot: change.slot,
+                previous_lamports: change.previous,
+                lamports: change.lamports,
+                delta: change.delta(),
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)
+        }
+        OutputFormat::Csv => writeln!(
+            out,
+            "{},{},{},{},{}",
+            change.wallet,
+            change.slot,
+            change.previous,
+            change.lamports,
+            change.delta(),
+        ),
+    }?;
+    out.flush()
+}
+
+pub fn print_snapshots(format: OutputFormat, snapshots: &[SnapshotInfo]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    if let OutputFormat::Csv = format {
+        writeln!(out, "id,taken_at,slot,rpc_url,wallets")?;
+    } else if let OutputFormat::Table = format {
+        writeln!(out, "{:>6}  {:<20}  {:>12}  {:>8}  RPC", "ID", "TAKEN AT", "SLOT", "WALLETS")?;
+    }
+    for snapshot in snapshots {
+        let taken_at = humantime::format_rfc3339_seconds(snapshot.taken_at).to_string();
+        let slot = snapshot.slot.map(|s| s.to_string()).unwrap_or_default();
+        match format {
+            OutputFormat::Table => writeln!(
+                out,
+                "{:>6}  {:<20}  {:>12}  {:>8}  {}",
+                snapshot.id, taken_at, slot, snapshot.wallets, snapshot.rpc_url
+            )?,
+            OutputFormat::Json => {
+                let record = Record::Snapshot {
+                    id: snapshot.id,
+                    taken_at,
+                    slot: snapshot.slot,
+                    rpc_url: &snapshot.rpc_url,
+                    wallets: snapshot.wallets,
+                };
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+            }
+            OutputFormat::Csv => writeln!(
+                out,
+                "{},{},{},{},{}",
+                snapshot.id,
+                taken_at,
+                slot,
+                csv_field(&snapshot.rpc_url),
+                snapshot.wallets
+            )?,
+        }
+    }
+    Ok(())
+}
+
+pub fn print_diff(format: OutputFormat, from: i64, to: i64, diff: &[BalanceDiff]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let amount = |lamports: Option<u64>| lamports.map(|l| l.to_string()).unwrap_or_else(|| "-".to_string());
+    match format {
+        OutputFormat::Table => {
+            writeln!(out, "Snapshot {} -> {}: {} wallets differ", from, to, diff.len())?;
+            for d in diff {
+                writeln!(
+                    out,
+                    "{:<44}  {:<11}  {:>20} -> {:<20}  ({:+})",
+                    d.wallet,
+                    change_name(d.kind),
+                    amount(d.before),
+                    amount(d.after),
+                    d.delta(),
+                )?;
+            }
+        }
+        OutputFormat::Json => {
+            for d in diff {
+                let record = Record::Diff {
+                    from,
+                    to,
+                    wallet: d.wallet.to_string(),
+                    change: d.kind,
+                    before: d.before,
+                    after: d.after,
+                    delta: d.delta(),
+                };
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "from,to,wallet,change,before,after,delta")?;
+            for d in diff {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{}",
+                    from,
+                    to,
+                    d.wallet,
+                    change_name(d.kind),
+                    d.before.map(|l| l.to_string()).unwrap_or_default(),
+                    d.after.map(|l| l.to_string()).unwrap_or_default(),
+                    d.delta(),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn change_name(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Changed => "changed",
+        ChangeKind::Appeared => "appeared",
+        ChangeKind::Disappeared => "disappeared",
+    }
+}
+
+/// Alerts are always JSON lines; with CSV output they go to stderr so the CSV stays parseable.
+pub fn print_alerts(format: OutputFormat, alerts: &[Alert]) -> io::Result<()> {
+    let mut out: Box<dyn Write> = match format {
+        OutputFormat::Csv => Box::new(io::stderr().lock()),
+        OutputFormat::Table | OutputFormat::Json => Box::new(io::stdout().lock()),
+    };
+    for alert in alerts {
+        serde_json::to_writer(&mut out, alert)?;
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scanner::Balance, tokens::TOKEN_PROGRAM_ID};
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use std::sync::Arc;
+
+    #[test]
+    fn summary_counts_errors_and_missing_accounts() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ok = |lamports, exists| Ok(Balance { lamports, exists, slot: 1 });
+        let error = Arc::new(ClientError::from(ClientErrorKind::Custom("boom".to_string())));
+        let reports = vec![
+            WalletReport { wallet: a, result: ok(1_500_000_000, true), tokens: None, mismatch: None },
+            WalletReport { wallet: a, result: ok(1_500_000_000, true), tokens: None, mismatch: None },
+            WalletReport { wallet: b, result: ok(0, false), tokens: None, mismatch: None },
+            WalletReport { wallet: c, result: Err(error), tokens: None, mismatch: None },
+        ];
+
+        let summary = Summary::from_reports(&reports);
+
+        assert_eq!(summary.wallets, 3);
+        assert_eq!(summary.total_lamports, 1_500_000_000);
+        assert_eq!(summary.total_sol, "1.500000000");
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.missing, 1);
+    }
+
+    #[test]
+    fn summary_counts_duplicated_wallets_once() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let missing = || Ok(Balance { lamports: 0, exists: false, slot: 1 });
+        let token_error = || Some(Err(Arc::new(ClientError::from(ClientErrorKind::Custom("boom".to_string())))));
+        let reports = vec![
+            WalletReport { wallet: a, result: missing(), tokens: token_error(), mismatch: None },
+            WalletReport {
+                wallet: b,
+                result: Ok(Balance { lamports: 7, exists: true, slot: 1 }),
+                tokens: None,
+                mismatch: None,
+            },
+            WalletReport { wallet: a, result: missing(), tokens: token_error(), mismatch: None },
+        ];
+
+        let summary = Summary::from_reports(&reports);
+        assert_eq!((summary.wallets, summary.missing, summary.token_errors), (2, 1, 1));
+        assert_eq!(summary.total_lamports, 7);
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &reports, &summary).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        // Every row has the header's column count.
+        let columns = lines[0].split(',').count();
+        assert!(lines.iter().all(|line| line.split(',').count() == columns));
+        let column = |name: &str| lines[0].split(',').position(|c| c == name).unwrap();
+        let totals: Vec<(&str, &str)> = lines
+            .iter()
+            .map(|line| line.split(',').collect::<Vec<_>>())
+            .filter(|fields| fields[column("record")] == "summary")
+            .map(|fields| (fields[column("key")], fields[column("value")]))
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                ("wallets", "2"),
+                ("errors", "0"),
+                ("not_found", "1"),
+                ("token_errors", "1"),
+                ("mismatches", "0"),
+                ("total_lamports", "7"),
+                ("total_sol", "0.000000007"),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_token_rows_carry_raw_and_ui_amounts() {
+        let (wallet, account, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let holding = TokenHolding { account, mint, program: TOKEN_PROGRAM_ID, amount: 1_234_500, decimals: 6 };
+        let reports = vec![WalletReport {
+            wallet,
+            result: Ok(Balance { lamports: 5, exists: true, slot: 1 }),
+            tokens: Some(Ok(vec![holding])),
+            mismatch: None,
+        }];
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &reports, &Summary::from_reports(&reports)).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",token_amount,ui_amount,decimals,key,value"));
+        assert_eq!(lines[2], format!("token,{},,,,,,,{},{},spl-token,1234500,1.234500,6,,", wallet, account, mint));
+    }
+
+    #[test]
+    fn format_units_pads_fraction() {
+        assert_eq!(format_units(1_234_500, 6), "1.234500");
+        assert_eq!(format_units(5, 3), "0.005");
+        assert_eq!(format_units(42, 0), "42");
+    }
+
+    #[test]
+    fn csv_field_quotes_special_characters() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+};
+use solana_rpc_client::{
+    http_sender::HttpSender,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{
+    retry::{self, AdaptiveLimiter, RetryPolicy},
+    scanner::{self, WalletReport},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Rotate through endpoints evenly.
+    RoundRobin,
+    /// Rotate through endpoints proportionally to their `#weight` suffix.
+    Weighted,
+}
+
+/// An RPC endpoint parsed from `URL[#WEIGHT]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointSpec {
+    pub url: String,
+    pub weight: usize,
+}
+
+impl EndpointSpec {
+    pub fn parse(spec: &str, resolve: impl Fn(&str) -> String) -> Result<Self, String> {
+        let (url, weight) = match spec.rsplit_once('#') {
+            Some((url, weight)) => {
+                let weight = weight
+                    .parse()
+                    .map_err(|_| format!("invalid endpoint weight in `{}`", spec))?;
+                (url, weight)
+            }
+            None => (spec, 1),
+        };
+        if weight == 0 {
+            return Err(format!("endpoint weight must be positive in `{}`", spec));
+        }
+        Ok(Self { url: resolve(url), weight })
+    }
+}
+
+pub struct Endpoint {
+    pub url: String,
+    pub limiter: Arc<AdaptiveLimiter>,
+    /// Client bound to this endpoint only, used for cross-checking.
+    pub client: Arc<RpcClient>,
+}
+
+/// Spreads JSON-RPC requests over several endpoints and fails over to the
+/// next one when a request fails for transport or node-health reasons.
+struct PoolSender {
+    senders: Vec<HttpSender>,
+    schedule: Vec<usize>,
+    cursor: AtomicUsize,
+}
+
+impl PoolSender {
+    fn is_failover(error: &ClientError) -> bool {
+        matches!(
+            error.kind(),
+            ClientErrorKind::Io(_)
+                | ClientErrorKind::Reqwest(_)
+                | ClientErrorKind::Middleware(_)
+                | ClientErrorKind::SerdeJson(_)
+                | ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    data: RpcResponseErrorData::NodeUnhealthy { .. },
+                    ..
+                })
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcSender for PoolSender {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let first = self.schedule[self.cursor.fetch_add(1, Ordering::Relaxed) % self.schedule.len()];
+        let mut last_error = None;
+        for offset in 0..self.senders.len() {
+            let sender = &self.senders[(first + offset) % self.senders.len()];
+            match sender.send(request, params.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if Self::is_failover(&e) => {
+                    eprintln!("RPC {} failed on {}: {}", request, sender.url(), e);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.expect("pool has at least one endpoint"))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.senders.iter().fold(RpcTransportStats::default(), |mut total, sender| {
+            let stats = sender.get_transport_stats();
+            total.request_count += stats.request_count;
+            total.elapsed_time += stats.elapsed_time;
+            total.rate_limited_time += stats.rate_limited_time;
+            total
+        })
+    }
+
+    fn url(&self) -> String {
+        self.senders[0].url()
+    }
+}
+
+/// Builds the endpoint visiting order: every index appears `weight` times, interleaved
+/// (smooth weighted round-robin) so heavier endpoints are not hit in bursts.
+fn build_schedule(weights: &[usize]) -> Vec<usize> {
+    let total: usize = weights.iter().sum();
+    let mut current = vec![0isize; weights.len()];
+    (0..total)
+        .map(|_| {
+            for (c, w) in current.iter_mut().zip(weights) {
+                *c += *w as isize;
+            }
+            let (best, _) = current
+                .iter()
+                .enumerate()
+                .max_by_key(|(i, c)| (**c, std::cmp::Reverse(*i)))
+                .expect("at least one endpoint");
+            current[best] -= total as isize;
+            best
+        })
+        .collect()
+}
+
+pub struct RpcPool {
+    pub endpoints: Vec<Endpoint>,
+    /// Client that load-balances and fails over across all endpoints.
+    pub client: Arc<RpcClient>,
+}
+
+impl RpcPool {
+    pub fn new(
+        specs: &[EndpointSpec],
+        strategy: Strategy,
+        commitment: CommitmentConfig,
+        policy: RetryPolicy,
+        concurrency: usize,
+    ) -> Self {
+        assert!(!specs.is_empty(), "at least one RPC endpoint is required");
+
+        let endpoints: Vec<Endpoint> = specs
+            .iter()
+            .map(|spec| {
+                let limiter = Arc::new(AdaptiveLimiter::new(&spec.url, concurrency));
+                let client = retry::build_client(spec.url.clone(), commitment, policy, Arc::clone(&limiter));
+                Endpoint { url: spec.url.clone(), limiter, client: Arc::new(client) }
+            })
+            .collect();
+
+        let weights: Vec<usize> = match strategy {
+            Strategy::RoundRobin => vec![1; specs.len()],
+            Strategy::Weighted => specs.iter().map(|s| s.weight).collect(),
+        };
+        let sender = PoolSender {
+            senders: endpoints
+                .iter()
+                .map(|e| retry::build_sender(e.url.clone(), policy, Arc::clone(&e.limiter)))
+                .collect(),
+            schedule: build_schedule(&weights),
+            cursor: AtomicUsize::new(0),
+        };
+        let client = RpcClient::new_sender(sender, RpcClientConfig::with_commitment(commitment));
+
+        Self { endpoints, client: Arc::new(client) }
+    }
+}
+
+/// Balance as seen by one provider during a cross-check.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderBalance {
+    pub url: String,
+    pub lamports: u64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub providers: Vec<ProviderBalance>,
+}
+
+/// Scans `wallets` on the first two endpoints independently and returns the primary
+/// endpoint's reports with `mismatch` set where the providers disagree on lamports.
+pub async fn cross_check(pool: &RpcPool, wallets: &[Pubkey], concurrency: usize) -> Vec<WalletReport> {
+    let (primary, secondary) = (&pool.endpoints[0], &pool.endpoints[1]);
+    let (mut reports, other) = tokio::join!(
+        scanner::scan_balances(Arc::clone(&primary.client), wallets, concurrency),
+        scanner::scan_balances(Arc::clone(&secondary.client), wallets, concurrency),
+    );
+
+    let other: HashMap<Pubkey, WalletReport> = other.into_iter().map(|r| (r.wallet, r)).collect();
+    for report in &mut reports {
+        let (Ok(mine), Some(Ok(theirs))) = (&report.result, other.get(&report.wallet).map(|r| &r.result)) else {
+            continue;
+        };
+        if mine.lamports != theirs.lamports {
+            report.mismatch = Some(Mismatch {
+                providers: vec![
+                    ProviderBalance { url: primary.url.clone(), lamports: mine.lamports, slot: mine.slot },
+                    ProviderBalance { url: secondary.url.clone(), lamports: theirs.lamports, slot: theirs.slot },
+                ],
+            });
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_spec_parses_optional_weight() {
+        let id = |s: &str| s.to_string();
+        assert_eq!(
+            EndpointSpec::parse("https://a.example#3", id).unwrap(),
+            EndpointSpec { url: "https://a.example".to_string(), weight: 3 }
+        );
+        assert_eq!(EndpointSpec::parse("https://b.example", id).unwrap().weight, 1);
+        assert!(EndpointSpec::parse("https://c.example#0", id).is_err());
+    }
+
+    #[test]
+    fn weighted_schedule_interleaves_endpoints() {
+        assert_eq!(build_schedule(&[1, 1]), vec![0, 1]);
+        assert_eq!(build_schedule(&[3, 1]), vec![0, 0, 1, 0]);
+    }
+}
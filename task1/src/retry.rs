@@ -0,0 +1,354 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::anyhow;
+use rand::Rng;
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
+use solana_client::{client_error::reqwest, nonblocking::rpc_client::RpcClient, rpc_client::RpcClientConfig};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use task_local_extensions::Extensions;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use reqwest::{header::RETRY_AFTER, Request, Response, StatusCode};
+
+/// Longest `Retry-After` we are willing to honour, matching the Solana HTTP sender.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+/// Successful requests needed before the limiter grows back by one slot.
+const RECOVERY_STREAK: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of the backoff added as random jitter, in `0.0..=1.0`.
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff before retry number `attempt` (starting at 1), without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    fn delay_with_jitter(&self, attempt: u32) -> Duration {
+        let delay = self.backoff(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 + rand::thread_rng().gen_range(0.0..=jitter))
+    }
+}
+
+/// Caps in-flight HTTP requests and halves the cap whenever the endpoint throttles us,
+/// growing it back one slot at a time after a streak of successful requests.
+pub struct AdaptiveLimiter {
+    endpoint: String,
+    semaphore: Arc<Semaphore>,
+    max: usize,
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    limit: usize,
+    successes: usize,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(endpoint: &str, max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            endpoint: endpoint.to_string(),
+            semaphore: Arc::new(Semaphore::new(max)),
+            max,
+            state: Mutex::new(LimiterState { limit: max, successes: 0 }),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("limiter semaphore is never closed")
+    }
+
+    fn on_throttle(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.successes = 0;
+        let reduced = (state.limit / 2).max(1);
+        let removed = state.limit - reduced;
+        if removed == 0 {
+            return;
+        }
+        state.limit = reduced;
+        eprintln!("RPC endpoint {} is throttling, lowering concurrency to {}", self.endpoint, reduced);
+
+        // Permits still held by in-flight requests are retired once they are released.
+        let semaphore = Arc::clone(&self.semaphore);
+        tokio::spawn(async move {
+            if let Ok(permits) = semaphore.acquire_many_owned(removed as u32).await {
+                permits.forget();
+            }
+        });
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.limit >= self.max {
+            return;
+        }
+        state.successes += 1;
+        if state.successes >= RECOVERY_STREAK {
+            state.successes = 0;
+            state.limit += 1;
+            self.semaphore.add_permits(1);
+        }
+    }
+}
+
+/// Retries throttled (429), server-side (5xx) and timed out JSON-RPC requests.
+struct RetryMiddleware {
+    policy: RetryPolicy,
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+#[async_trait::async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let mut attempt = 1;
+        loop {
+            let request = req
+                .try_clone()
+                .ok_or_else(|| anyhow!("RPC request body cannot be retried"))?;
+            let outcome = {
+                let _permit = self.limiter.acquire().await;
+                next.clone().run(request, extensions).await
+            };
+
+            let retry_after = match &outcome {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    self.limiter.on_throttle();
+                    let header = response.headers().get(RETRY_AFTER).and_then(|value| value.to_str().ok());
+                    Some(header.and_then(|value| parse_retry_after(value, SystemTime::now())))
+                }
+                Ok(response) if response.status().is_server_error() => Some(None),
+                Err(reqwest_middleware::Error::Reqwest(e)) if e.is_timeout() => Some(None),
+                Ok(_) => {
+                    self.limiter.on_success();
+                    None
+                }
+                Err(_) => None,
+            };
+            let Some(retry_after) = retry_after else {
+                return outcome;
+            };
+
+            if attempt >= self.policy.max_attempts {
+                return match outcome {
+                    // Surface throttling as an error so the HTTP sender does not start its own 429 loop.
+                    Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => Err(anyhow!(
+                        "RPC endpoint is still rate limiting (HTTP 429) after {} attempts",
+                        attempt
+                    )
+                    .into()),
+                    other => other,
+                };
+            }
+
+            let delay = retry_after.unwrap_or_else(|| self.policy.delay_with_jitter(attempt));
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Parses a `Retry-After` value, either delta-seconds or an HTTP-date (RFC 9110 §10.2.3).
+/// Dates in the past mean "retry now".
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            date.duration_since(now).unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// Builds an HTTP sender that retries according to `policy` and shares
+/// `limiter` across every request it sends.
+pub fn build_sender(url: String, policy: RetryPolicy, limiter: Arc<AdaptiveLimiter>) -> HttpSender {
+    let timeout = Duration::from_secs(30);
+    let http = reqwest::Client::builder()
+        .default_headers(HttpSender::default_headers())
+        .timeout(timeout)
+        .pool_idle_timeout(timeout)
+        .build()
+        .expect("build rpc client");
+    let http = ClientBuilder::new(http)
+        .with(RetryMiddleware { policy, limiter })
+        .build();
+
+    HttpSender::new_with_client_with_middleware(url, http)
+}
+
+pub fn build_client(
+    url: String,
+    commitment: CommitmentConfig,
+    policy: RetryPolicy,
+    limiter: Arc<AdaptiveLimiter>,
+) -> RpcClient {
+    RpcClient::new_sender(
+        build_sender(url, policy, limiter),
+        RpcClientConfig::with_commitment(commitment),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            jitter: 0.0,
+        }
+    }
+
+    /// Serves one request per connection, answering with `responses` in order;
+    /// `None` holds the connection open without answering. Returns the URL and
+    /// a counter of requests received.
+    async fn stand_in(responses: Vec<Option<&'static str>>) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !String::from_utf8_lossy(&request).contains("\r\n\r\n{}") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                match response {
+                    Some(response) => socket.write_all(response.as_bytes()).await.unwrap(),
+                    None => {
+                        tokio::spawn(async move {
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                            drop(socket);
+                        });
+                    }
+                }
+            }
+        });
+        (url, requests)
+    }
+
+    async fn post(url: &str, policy: RetryPolicy) -> reqwest_middleware::Result<Response> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+        let limiter = Arc::new(AdaptiveLimiter::new(url, 4));
+        let http = ClientBuilder::new(http)
+            .with(RetryMiddleware { policy, limiter })
+            .build();
+        http.post(url).body("{}").send().await
+    }
+
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+    const THROTTLED: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = httpdate::parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(parse_retry_after(" 7 ", now), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after("86400", now), Some(MAX_RETRY_AFTER));
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Mon, 07 Nov 1994 08:49:37 GMT", now), Some(MAX_RETRY_AFTER));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn retries_throttled_and_server_errors_until_success() {
+        let (url, requests) = stand_in(vec![Some(THROTTLED), Some(UNAVAILABLE), Some(OK)]).await;
+        let response = post(&url, policy(5)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_timed_out_requests() {
+        let (url, requests) = stand_in(vec![None, Some(OK)]).await;
+        let response = post(&url, policy(3)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn persistent_throttling_becomes_an_error() {
+        let (url, requests) = stand_in(vec![Some(THROTTLED), Some(THROTTLED)]).await;
+        let error = post(&url, policy(2)).await.unwrap_err();
+        assert!(error.to_string().contains("HTTP 429"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter: 0.0,
+        };
+        let delays: Vec<_> = (1..=6).map(|attempt| policy.backoff(attempt).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[tokio::test]
+    async fn limiter_halves_on_throttle_and_recovers() {
+        let limiter = AdaptiveLimiter::new("http://localhost", 8);
+        limiter.on_throttle();
+        limiter.on_throttle();
+        assert_eq!(limiter.limit(), 2);
+
+        for _ in 0..RECOVERY_STREAK {
+            limiter.on_success();
+        }
+        assert_eq!(limiter.limit(), 3);
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_response::Response,
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::sync::Semaphore;
+
+use crate::{pool::Mismatch, tokens::TokenResult};
+
+/// Upper bound on pubkeys accepted by a single `getMultipleAccounts` call.
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Balance {
+    pub lamports: u64,
+    pub exists: bool,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletReport {
+    pub wallet: Pubkey,
+    pub result: Result<Balance, Arc<ClientError>>,
+    /// Token holdings, present only when token scanning was requested.
+    pub tokens: Option<TokenResult>,
+    /// Set by a cross-check when two providers disagree on the balance.
+    pub mismatch: Option<Mismatch>,
+}
+
+/// Returns the wallets with duplicates removed, keeping the first occurrence of each.
+pub fn dedup_wallets(wallets: &[Pubkey]) -> Vec<Pubkey> {
+    let mut seen = HashSet::with_capacity(wallets.len());
+    wallets.iter().copied().filter(|w| seen.insert(*w)).collect()
+}
+
+/// Fetches the balance of every wallet with batched `getMultipleAccounts` calls.
+/// Reports come back in the same order (and multiplicity) as `wallets`; a failed
+/// batch marks each of its wallets with the batch error.
+pub async fn scan_balances(
+    client: Arc<RpcClient>,
+    wallets: &[Pubkey],
+    concurrency: usize,
+) -> Vec<WalletReport> {
+    let unique = dedup_wallets(wallets);
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = FuturesUnordered::new();
+
+    for chunk in unique.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        let client = Arc::clone(&client);
+        let sem = Arc::clone(&semaphore);
+        tasks.push(async move {
+            let _permit = sem.acquire_owned().await;
+            let result = client
+                .get_multiple_accounts_with_commitment(chunk, client.commitment())
+                .await;
+            (chunk, result)
+        });
+    }
+
+    let mut balances = HashMap::with_capacity(unique.len());
+    while let Some((chunk, result)) = tasks.next().await {
+        record_chunk(&mut balances, chunk, result);
+    }
+
+    assemble_reports(wallets, &balances)
+}
+
+type Balances = HashMap<Pubkey, Result<Balance, Arc<ClientError>>>;
+
+fn custom_error(message: String) -> Arc<ClientError> {
+    Arc::new(ClientError::from(ClientErrorKind::Custom(message)))
+}
+
+/// Stores the balances from one `getMultipleAccounts` response. Accounts are matched to
+/// wallets by position, so a response of the wrong length fails the whole chunk.
+fn record_chunk(
+    balances: &mut Balances,
+    chunk: &[Pubkey],
+    result: Result<Response<Vec<Option<Account>>>, ClientError>,
+) {
+    let response = match result {
+        Ok(response) if response.value.len() == chunk.len() => response,
+        Ok(response) => {
+            let e = custom_error(format!(
+                "getMultipleAccounts returned {} accounts for {} requested",
+                response.value.len(),
+                chunk.len()
+            ));
+            for wallet in chunk {
+                balances.insert(*wallet, Err(Arc::clone(&e)));
+            }
+            return;
+        }
+        Err(e) => {
+            let e = Arc::new(e);
+            for wallet in chunk {
+                balances.insert(*wallet, Err(Arc::clone(&e)));
+            }
+            return;
+        }
+    };
+    let slot = response.context.slot;
+    for (wallet, account) in chunk.iter().zip(response.value) {
+        let balance = Balance {
+            lamports: account.as_ref().map_or(0, |a| a.lamports),
+            exists: account.is_some(),
+            slot,
+        };
+        balances.insert(*wallet, Ok(balance));
+    }
+}
+
+fn assemble_reports(wallets: &[Pubkey], balances: &Balances) -> Vec<WalletReport> {
+    wallets
+        .iter()
+        .map(|wallet| WalletReport {
+            wallet: *wallet,
+            result: balances
+                .get(wallet)
+                .cloned()
+                .unwrap_or_else(|| Err(custom_error(format!("no balance returned for {}", wallet)))),
+            tokens: None,
+            mismatch: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_response::RpcResponseContext;
+
+    #[test]
+    fn dedup_keeps_first_occurrence_order() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(dedup_wallets(&[b, a, b, c, a]), vec![b, a, c]);
+    }
+
+    #[test]
+    fn reports_follow_input_order_with_duplicates() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let balance = Balance { lamports: 42, exists: true, slot: 7 };
+        let error = custom_error("boom".to_string());
+        let balances = HashMap::from([(a, Ok(balance)), (b, Err(error))]);
+
+        let reports = assemble_reports(&[b, a, b], &balances);
+
+        let wallets: Vec<_> = reports.iter().map(|r| r.wallet).collect();
+        assert_eq!(wallets, vec![b, a, b]);
+        assert!(reports[0].result.is_err());
+        assert_eq!(reports[1].result.as_ref().unwrap(), &balance);
+        assert!(reports[2].result.is_err());
+    }
+
+    #[test]
+    fn short_response_fails_its_chunk_instead_of_panicking() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let account = Account { lamports: 5, ..Account::default() };
+        let response = |value| Response { context: RpcResponseContext::new(9), value };
+        let mut balances = HashMap::new();
+        record_chunk(&mut balances, &[a, b], Ok(response(vec![Some(account)])));
+        record_chunk(&mut balances, &[c], Ok(response(vec![None])));
+
+        let reports = assemble_reports(&[a, b, c, a], &balances);
+
+        assert!(reports[0].result.as_ref().unwrap_err().to_string().contains("returned 1 accounts for 2"));
+        assert!(reports[1].result.is_err());
+        assert_eq!(reports[2].result.as_ref().unwrap(), &Balance { lamports: 0, exists: false, slot: 9 });
+        assert!(reports[3].result.is_err());
+        assert!(assemble_reports(&[Pubkey::new_unique()], &balances)[0].result.is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::scanner::WalletReport;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        taken_at INTEGER NOT NULL,
+        slot     INTEGER,
+        rpc_url  TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS balances (
+        snapshot_id    INTEGER NOT NULL REFERENCES snapshots(id),
+        wallet         TEXT NOT NULL,
+        lamports       INTEGER,
+        account_exists INTEGER,
+        slot           INTEGER,
+        PRIMARY KEY (snapshot_id, wallet)
+    );
+";
+
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub id: i64,
+    pub taken_at: SystemTime,
+    pub slot: Option<u64>,
+    pub rpc_url: String,
+    pub wallets: usize,
+}
+
+/// What a snapshot recorded for one wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WalletState {
+    Balance(u64),
+    /// The lookup succeeded but the account did not exist.
+    Missing,
+    /// The lookup failed, so the balance is unknown; `diff` skips such wallets.
+    Unknown,
+}
+
+impl WalletState {
+    fn lamports(self) -> Option<u64> {
+        match self {
+            WalletState::Balance(lamports) => Some(lamports),
+            WalletState::Missing | WalletState::Unknown => None,
+        }
+    }
+}
+
+/// Wallets of one snapshot; a wallet that was not scanned has no entry at all.
+type SnapshotBalances = HashMap<Pubkey, WalletState>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Changed,
+    Appeared,
+    Disappeared,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceDiff {
+    pub wallet: Pubkey,
+    pub kind: ChangeKind,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl BalanceDiff {
+    pub fn delta(&self) -> i128 {
+        self.after.unwrap_or(0) as i128 - self.before.unwrap_or(0) as i128
+    }
+}
+
+pub struct SnapshotStore {
+    conn: Connection,
+}
+
+impl SnapshotStore {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Stores one scan and returns the new snapshot id.
+    pub fn record(&mut self, rpc_url: &str, reports: &[WalletReport]) -> rusqlite::Result<i64> {
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let slot = reports
+            .iter()
+            .filter_map(|r| r.result.as_ref().ok().map(|b| b.slot as i64))
+            .max();
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO snapshots (taken_at, slot, rpc_url) VALUES (?1, ?2, ?3)",
+            params![taken_at, slot, rpc_url],
+        )?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO balances (snapshot_id, wallet, lamports, account_exists, slot)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for report in reports {
+                let (lamports, exists, slot) = match &report.result {
+                    Ok(balance) => (Some(balance.lamports as i64), Some(balance.exists), Some(balance.slot as i64)),
+                    Err(_) => (None, None, None),
+                };
+                insert.execute(params![id, report.wallet.to_string(), lamports, exists, slot])?;
+            }
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn list(&self) -> rusqlite::Result<Vec<SnapshotInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.taken_at, s.slot, s.rpc_url, COUNT(b.wallet)
+             FROM snapshots s LEFT JOIN balances b ON b.snapshot_id = s.id
+             GROUP BY s.id ORDER BY s.id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SnapshotInfo {
+                id: row.get(0)?,
+                taken_at: UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(1)? as u64),
+                slot: row.get::<_, Option<i64>>(2)?.map(|s| s as u64),
+                rpc_url: row.get(3)?,
+                wallets: row.get::<_, i64>(4)? as usize,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Ids of the two most recent snapshots, oldest first.
+    pub fn latest_pair(&self) -> rusqlite::Result<Option<(i64, i64)>> {
+        self.conn
+            .query_row(
+                "SELECT MIN(id), MAX(id) FROM (SELECT id FROM snapshots ORDER BY id DESC LIMIT 2) HAVING COUNT(*) = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+
+    pub fn exists(&self, id: i64) -> rusqlite::Result<bool> {
+        self.conn
+            .query_row("SELECT 1 FROM snapshots WHERE id = ?1", [id], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+    }
+
+    fn load(&self, id: i64) -> rusqlite::Result<SnapshotBalances> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT wallet, lamports, account_exists FROM balances WHERE snapshot_id = ?1",
+            )?;
+        let rows = stmt.query_map([id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, Option<bool>>(2)?))
+        })?;
+
+        let mut balances = SnapshotBalances::new();
+        for row in rows {
+            let (wallet, lamports, exists) = row?;
+            // Rows are only ever written from parsed pubkeys, so this cannot fail on our own files.
+            let Ok(wallet) = wallet.parse() else { continue };
+            let state = match (exists, lamports) {
+                (Some(true), Some(lamports)) => WalletState::Balance(lamports as u64),
+                (Some(false), _) => WalletState::Missing,
+                _ => WalletState::Unknown,
+            };
+            balances.insert(wallet, state);
+        }
+        Ok(balances)
+    }
+
+    pub fn diff(&self, from: i64, to: i64) -> rusqlite::Result<Vec<BalanceDiff>> {
+        Ok(diff_balances(&self.load(from)?, &self.load(to)?))
+    }
+}
+
+fn diff_balances(before: &SnapshotBalances, after: &SnapshotBalances) -> Vec<BalanceDiff> {
+    let wallets: BTreeSet<Pubkey> = before.keys().chain(after.keys()).copied().collect();
+    wallets
+        .into_iter()
+        .filter_map(|wallet| {
+            let (old, new) = (before.get(&wallet).copied(), after.get(&wallet).copied());
+            if old == Some(WalletState::Unknown) || new == Some(WalletState::Unknown) {
+                return None;
+            }
+            // A wallet missing from a snapshot and an account that did not exist both have no balance.
+            let (old, new) = (old.and_then(WalletState::lamports), new.and_then(WalletState::lamports));
+            let kind = match (old, new) {
+                (Some(a), Some(b)) if a != b => ChangeKind::Changed,
+                (None, Some(_)) => ChangeKind::Appeared,
+                (Some(_), None) => ChangeKind::Disappeared,
+                _ => return None,
+            };
+            Some(BalanceDiff { wallet, kind, before: old, after: new })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Balance;
+    use solana_client::client_error::{ClientError, ClientErrorKind};
+    use std::sync::Arc;
+
+    fn report(wallet: Pubkey, lamports: u64, exists: bool) -> WalletReport {
+        WalletReport {
+            wallet,
+            result: Ok(Balance { lamports, exists, slot: 10 }),
+            tokens: None,
+            mismatch: None,
+        }
+    }
+
+    #[test]
+    fn diff_reports_changed_appeared_and_disappeared() {
+        let (changed, same, gone, new, created) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut store = SnapshotStore::init(Connection::open_in_memory().unwrap()).unwrap();
+        let first = store
+            .record(
+                "http://localhost",
+                &[report(changed, 10, true), report(same, 5, true), report(gone, 7, true), report(created, 0, false)],
+            )
+            .unwrap();
+        let second = store
+            .record(
+                "http://localhost",
+                &[report(changed, 15, true), report(same, 5, true), report(new, 3, true), report(created, 0, true)],
+            )
+            .unwrap();
+
+        assert_eq!(store.latest_pair().unwrap(), Some((first, second)));
+        let mut diff = store.diff(first, second).unwrap();
+        diff.sort_by_key(|d| d.wallet);
+
+        let mut expected = vec![
+            BalanceDiff { wallet: changed, kind: ChangeKind::Changed, before: Some(10), after: Some(15) },
+            BalanceDiff { wallet: gone, kind: ChangeKind::Disappeared, before: Some(7), after: None },
+            BalanceDiff { wallet: new, kind: ChangeKind::Appeared, before: None, after: Some(3) },
+            BalanceDiff { wallet: created, kind: ChangeKind::Appeared, before: None, after: Some(0) },
+        ];
+        expected.sort_by_key(|d| d.wallet);
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    fn diff_skips_wallets_with_failed_lookups() {
+        let (flaky, changed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let failed = WalletReport {
+            wallet: flaky,
+            result: Err(Arc::new(ClientError::from(ClientErrorKind::Custom("rpc timeout".to_string())))),
+            tokens: None,
+            mismatch: None,
+        };
+        let mut store = SnapshotStore::init(Connection::open_in_memory().unwrap()).unwrap();
+        let first = store.record("http://localhost", &[report(flaky, 10, true), report(changed, 1, true)]).unwrap();
+        let second = store.record("http://localhost", &[failed, report(changed, 2, true)]).unwrap();
+
+        assert_eq!(store.load(second).unwrap()[&flaky], WalletState::Unknown);
+        let diff = store.diff(first, second).unwrap();
+        assert_eq!(diff, vec![BalanceDiff { wallet: changed, kind: ChangeKind::Changed, before: Some(1), after: Some(2) }]);
+        assert!(store.diff(second, first).unwrap().iter().all(|d| d.wallet != flaky));
+    }
+}
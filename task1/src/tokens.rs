@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Deserialize;
+use solana_account_decoder_client_types::{token::UiTokenAmount, UiAccountData};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::TokenAccountsFilter,
+    rpc_response::RpcKeyedAccount,
+};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use tokio::sync::Semaphore;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHolding {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub program: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+pub type TokenResult = Result<Vec<TokenHolding>, Arc<ClientError>>;
+
+#[derive(Deserialize)]
+struct ParsedTokenAccount {
+    info: TokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenAccountInfo {
+    mint: String,
+    token_amount: UiTokenAmount,
+}
+
+/// Lists the SPL Token and Token-2022 accounts owned by each wallet.
+/// When `mints` is non-empty only accounts of those mints are kept.
+pub async fn scan_token_holdings(
+    client: Arc<RpcClient>,
+    wallets: &[Pubkey],
+    mints: &HashSet<Pubkey>,
+    concurrency: usize,
+) -> HashMap<Pubkey, TokenResult> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = FuturesUnordered::new();
+
+    for wallet in wallets.iter().copied() {
+        for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            let client = Arc::clone(&client);
+            let sem = Arc::clone(&semaphore);
+            tasks.push(async move {
+                let _permit = sem.acquire_owned().await;
+                let result = match client
+                    .get_token_accounts_by_owner_with_commitment(
+                        &wallet,
+                        TokenAccountsFilter::ProgramId(program),
+                        client.commitment(),
+                    )
+                    .await
+                {
+                    Ok(response) => response
+                        .value
+                        .iter()
+                        .map(|keyed| parse_holding(keyed, program))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|reason| ClientError::from(ClientErrorKind::Custom(reason))),
+                    Err(e) => Err(e),
+                };
+                (wallet, result)
+            });
+        }
+    }
+
+    let mut per_program: HashMap<Pubkey, Vec<Result<Vec<TokenHolding>, ClientError>>> =
+        HashMap::with_capacity(wallets.len());
+    while let Some((wallet, result)) = tasks.next().await {
+        per_program.entry(wallet).or_default().push(result);
+    }
+    per_program
+        .into_iter()
+        .map(|(wallet, results)| (wallet, merge_holdings(results, mints)))
+        .collect()
+}
+
+/// Combines the per-program lists of one wallet, keeping only `mints` when it is
+/// non-empty. An error from any program fails the wallet's whole token section.
+fn merge_holdings(results: Vec<Result<Vec<TokenHolding>, ClientError>>, mints: &HashSet<Pubkey>) -> TokenResult {
+    let mut holdings = Vec::new();
+    for result in results {
+        let found = result.map_err(Arc::new)?;
+        holdings.extend(found.into_iter().filter(|h| mints.is_empty() || mints.contains(&h.mint)));
+    }
+    holdings.sort_by_key(|h| (h.program, h.mint, h.account));
+    Ok(holdings)
+}
+
+fn parse_holding(keyed: &RpcKeyedAccount, program: Pubkey) -> Result<TokenHolding, String> {
+    let invalid = |reason: String| format!("unexpected token account {}: {}", keyed.pubkey, reason);
+
+    let UiAccountData::Json(parsed) = &keyed.account.data else {
+        return Err(invalid("response is not jsonParsed".to_string()));
+    };
+    let token: ParsedTokenAccount =
+        serde_json::from_value(parsed.parsed.clone()).map_err(|e| invalid(e.to_string()))?;
+
+    Ok(TokenHolding {
+        account: keyed.pubkey.parse().map_err(|e| invalid(format!("{}", e)))?,
+        mint: token.info.mint.parse().map_err(|e| invalid(format!("{}", e)))?,
+        program,
+        amount: token.info.token_amount.amount.parse().map_err(|e| invalid(format!("{}", e)))?,
+        decimals: token.info.token_amount.decimals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_json_parsed_token_account() {
+        let mint = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let keyed: RpcKeyedAccount = serde_json::from_value(json!({
+            "pubkey": account.to_string(),
+            "account": {
+                "lamports": 2039280,
+                "owner": TOKEN_PROGRAM_ID.to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": 165,
+                "data": {
+                    "program": "spl-token",
+                    "space": 165,
+                    "parsed": {
+                        "type": "account",
+                        "info": {
+                            "mint": mint.to_string(),
+                            "owner": Pubkey::new_unique().to_string(),
+                            "state": "initialized",
+                            "isNative": false,
+                            "tokenAmount": {
+                                "amount": "1234500",
+                                "decimals": 6,
+                                "uiAmount": 1.2345,
+                                "uiAmountString": "1.2345"
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let holding = parse_holding(&keyed, TOKEN_PROGRAM_ID).unwrap();
+
+        assert_eq!(
+            holding,
+            TokenHolding { account, mint, program: TOKEN_PROGRAM_ID, amount: 1_234_500, decimals: 6 }
+        );
+    }
+
+    fn holding(program: Pubkey, mint: Pubkey) -> TokenHolding {
+        TokenHolding { account: Pubkey::new_unique(), mint, program, amount: 1, decimals: 0 }
+    }
+
+    #[test]
+    fn merges_both_programs_sorted() {
+        let (usdc, pyusd) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let classic = holding(TOKEN_PROGRAM_ID, usdc);
+        let extended = holding(TOKEN_2022_PROGRAM_ID, pyusd);
+
+        let merged = merge_holdings(vec![Ok(vec![extended.clone()]), Ok(vec![classic.clone()])], &HashSet::new());
+
+        let mut expected = vec![classic, extended];
+        expected.sort_by_key(|h| (h.program, h.mint, h.account));
+        assert_eq!(merged.unwrap(), expected);
+    }
+
+    #[test]
+    fn mint_allowlist_filters_every_program() {
+        let (usdc, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let kept = holding(TOKEN_2022_PROGRAM_ID, usdc);
+        let results = vec![
+            Ok(vec![holding(TOKEN_PROGRAM_ID, other)]),
+            Ok(vec![kept.clone(), holding(TOKEN_2022_PROGRAM_ID, other)]),
+        ];
+
+        assert_eq!(merge_holdings(results, &HashSet::from([usdc])).unwrap(), vec![kept]);
+    }
+
+    #[test]
+    fn error_from_one_program_overrides_the_other() {
+        let found = Ok(vec![holding(TOKEN_PROGRAM_ID, Pubkey::new_unique())]);
+        let failed = Err(ClientError::from(ClientErrorKind::Custom("token-2022 down".to_string())));
+
+        let error = merge_holdings(vec![found, failed], &HashSet::new()).unwrap_err();
+        assert!(error.to_string().contains("token-2022 down"));
+        let failed = Err(ClientError::from(ClientErrorKind::Custom("token down".to_string())));
+        assert!(merge_holdings(vec![failed, Ok(Vec::new())], &HashSet::new()).is_err());
+    }
+}
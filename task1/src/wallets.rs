@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fmt};
+
+use solana_sdk::pubkey::{Pubkey, PUBKEY_BYTES};
+use yaml_rust2::{
+    parser::{MarkedEventReceiver, Parser},
+    scanner::Marker,
+    Event,
+};
+
+/// Validates a base58 pubkey, explaining what is wrong with it instead of the
+/// generic "invalid" / "wrong size" errors of `Pubkey::from_str`.
+pub fn check_pubkey(s: &str) -> Result<Pubkey, String> {
+    let bytes = bs58::decode(s)
+        .into_vec()
+        .map_err(|e| format!("not valid base58: {}", e))?;
+    Pubkey::try_from(bytes.as_slice())
+        .map_err(|_| format!("decodes to {} bytes, expected {}", bytes.len(), PUBKEY_BYTES))
+}
+
+/// Resolves a single pubkey-or-label entry the same way `resolve_wallets` treats
+/// `public_keys` entries.
+pub fn resolve_wallet(entry: &str, labels: &HashMap<String, String>) -> Result<Pubkey, String> {
+    let entry = entry.trim();
+    check_pubkey(entry).or_else(|reason| match labels.get(entry) {
+        Some(value) => check_pubkey(value).map_err(|_| format!("label `{}` does not map to a valid pubkey", entry)),
+        None => Err(reason),
+    })
+}
+
+/// 1-based source lines of the wallet entries in the YAML config.
+#[derive(Debug, Default)]
+pub struct EntryLines {
+    pub public_keys: Vec<usize>,
+    pub labels: HashMap<String, usize>,
+}
+
+#[derive(Default)]
+struct EventCollector(Vec<(Event, Marker)>);
+
+impl MarkedEventReceiver for EventCollector {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        self.0.push((event, mark));
+    }
+}
+
+impl EntryLines {
+    /// Best effort: the config crate has already accepted the file, so a parse
+    /// failure here only means the messages go out without line numbers.
+    pub fn locate(source: &str) -> Self {
+        let mut events = EventCollector::default();
+        if Parser::new_from_str(source).load(&mut events, false).is_err() {
+            return Self::default();
+        }
+
+        let mut lines = Self::default();
+        let mut depth = 0;
+        // Top-level key whose value is currently being walked, and the key waiting for its value.
+        let (mut section, mut pending) = (None::<String>, None::<String>);
+        let mut label = None::<String>;
+        for (event, mark) in events.0 {
+            match event {
+                Event::MappingStart(..) | Event::SequenceStart(..) => {
+                    if depth == 1 {
+                        section = pending.take();
+                    }
+                    depth += 1;
+                }
+                Event::MappingEnd | Event::SequenceEnd => {
+                    depth -= 1;
+                    if depth == 1 {
+                        section = None;
+                    }
+                }
+                Event::Scalar(value, ..) if depth == 1 => {
+                    // A scalar either names a key or is the value of the pending one.
+                    pending = if pending.is_some() { None } else { Some(value) };
+                }
+                Event::Scalar(value, ..) if depth == 2 => match section.as_deref() {
+                    Some("public_keys") => lines.public_keys.push(mark.line()),
+                    Some("labels") => match label.take() {
+                        Some(name) => {
+                            lines.labels.insert(name, mark.line());
+                        }
+                        None => label = Some(value),
+                    },
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        lines
+    }
+}
+
+/// A config entry that could not be turned into a pubkey.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidEntry {
+    pub line: Option<usize>,
+    pub entry: String,
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid wallet `{}`: {}", self.entry, self.reason)
+    }
+}
+
+/// Resolves `public_keys` entries, which are either pubkeys or names from `labels`.
+/// Invalid entries (and invalid labels) are returned alongside the valid wallets
+/// so the scan can go ahead with whatever is usable.
+pub fn resolve_wallets(
+    entries: &[String],
+    labels: &HashMap<String, String>,
+    lines: &EntryLines,
+) -> (Vec<Pubkey>, Vec<InvalidEntry>) {
+    let mut invalid = Vec::new();
+
+    let mut names: Vec<&String> = labels.keys().collect();
+    names.sort_by_key(|name| lines.labels.get(*name));
+    let mut resolved = HashMap::new();
+    for name in names {
+        let value = &labels[name];
+        match check_pubkey(value) {
+            Ok(pubkey) => {
+                resolved.insert(name.as_str(), Ok(pubkey));
+            }
+            Err(reason) => {
+                invalid.push(InvalidEntry {
+                    line: lines.labels.get(name).copied(),
+                    entry: value.clone(),
+                    reason: format!("{} (label `{}`)", reason, name),
+                });
+                resolved.insert(name.as_str(), Err(()));
+            }
+        }
+    }
+
+    let mut wallets = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        let entry = entry.trim();
+        let reason = match check_pubkey(entry) {
+            Ok(pubkey) => {
+                wallets.push(pubkey);
+                continue;
+            }
+            Err(reason) => match resolved.get(entry) {
+                Some(Ok(pubkey)) => {
+                    wallets.push(*pubkey);
+                    continue;
+                }
+                Some(Err(())) => format!("label `{}` does not map to a valid pubkey", entry),
+                None => reason,
+            },
+        };
+        invalid.push(InvalidEntry {
+            line: lines.public_keys.get(i).copied(),
+            entry: entry.to_string(),
+            reason,
+        });
+    }
+    (wallets, invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+public_keys:
+  - \"5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHV\"
+  - treasury
+  - \"5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHl\"
+  - \"7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6j\"
+  - broken
+labels:
+  treasury: \"7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6jmE\"
+  broken: \"not-a-key\"
+token_mints: []
+";
+
+    #[test]
+    fn resolves_labels_and_reports_invalid_entries_with_lines() {
+        let entries: Vec<String> = ["5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHV", "treasury", "5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHl", "7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6j", "broken"]
+            .map(String::from)
+            .to_vec();
+        let labels = HashMap::from([
+            ("treasury".to_string(), "7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6jmE".to_string()),
+            ("broken".to_string(), "not-a-key".to_string()),
+        ]);
+        let lines = EntryLines::locate(CONFIG);
+        assert_eq!(lines.public_keys, vec![2, 3, 4, 5, 6]);
+        assert_eq!(lines.labels["broken"], 9);
+
+        let (wallets, invalid) = resolve_wallets(&entries, &labels, &lines);
+        assert_eq!(
+            wallets,
+            vec![
+                "5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHV".parse().unwrap(),
+                "7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6jmE".parse().unwrap(),
+            ]
+        );
+        let found: Vec<(Option<usize>, &str)> = invalid.iter().map(|e| (e.line, e.entry.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(9), "not-a-key"),
+                (Some(4), "5ZjxMYBbnKd4VFxLjAChSWMTeQ96147HnxZvQJxUseHl"),
+                (Some(5), "7SemrpW1SnhndK2ceWaRQKeAbTY7LdBaA1ctUmFg6j"),
+                (Some(6), "broken"),
+            ]
+        );
+        assert!(invalid[1].reason.starts_with("not valid base58"));
+        assert!(invalid[2].reason.starts_with("decodes to 31 bytes"));
+    }
+}
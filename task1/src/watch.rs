@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{self, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::stream::{self, StreamExt};
+use solana_account_decoder_client_types::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcAccountInfoConfig,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    output::{self, BalanceChange, OutputFormat},
+    scanner,
+};
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Last known balance of a wallet and the slot it was observed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Seen {
+    lamports: u64,
+    slot: u64,
+}
+
+/// Exponential reconnect delay that starts over once a session received data.
+struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Self { delay: INITIAL_RECONNECT_DELAY }
+    }
+
+    fn next(&mut self, received: bool) -> Duration {
+        if received {
+            self.delay = INITIAL_RECONNECT_DELAY;
+        }
+        let delay = self.delay;
+        self.delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        delay
+    }
+}
+
+/// Streams lamport changes for `wallets` until the process is interrupted.
+/// Every (re)connection subscribes first and then re-reads balances over RPC, so
+/// changes that happened while the websocket was down are still reported.
+pub async fn watch_balances(
+    client: Arc<RpcClient>,
+    ws_url: &str,
+    wallets: &[Pubkey],
+    format: OutputFormat,
+    concurrency: usize,
+) -> Result<(), Box<dyn Error>> {
+    let wallets = scanner::dedup_wallets(wallets);
+    let mut last_seen: HashMap<Pubkey, Seen> = HashMap::with_capacity(wallets.len());
+    let mut backoff = Backoff::new();
+
+    output::print_change_header(format)?;
+    loop {
+        let delay = match session(&client, ws_url, &wallets, concurrency, format, &mut last_seen).await {
+            Ok(received) => {
+                let delay = backoff.next(received);
+                eprintln!("websocket closed, resubscribing in {:?}", delay);
+                delay
+            }
+            Err(e) => {
+                let delay = backoff.next(false);
+                eprintln!("websocket error: {}, resubscribing in {:?}", e, delay);
+                delay
+            }
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// One websocket connection: subscribe to every wallet, resync over RPC, then stream.
+/// Returns `Ok(true)` when at least one notification arrived before the stream ended.
+async fn session(
+    client: &Arc<RpcClient>,
+    ws_url: &str,
+    wallets: &[Pubkey],
+    concurrency: usize,
+    format: OutputFormat,
+    last_seen: &mut HashMap<Pubkey, Seen>,
+) -> Result<bool, Box<dyn Error>> {
+    let pubsub = PubsubClient::new(ws_url).await?;
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+        commitment: Some(client.commitment()),
+        min_context_slot: None,
+    };
+
+    let mut subscriptions = Vec::with_capacity(wallets.len());
+    for wallet in wallets.iter().copied() {
+        let (notifications, _unsubscribe) = pubsub.account_subscribe(&wallet, Some(config.clone())).await?;
+        subscriptions.push(notifications.map(move |response| (wallet, response)));
+    }
+    eprintln!("subscribed to {} accounts via {}", wallets.len(), ws_url);
+
+    // Notifications queue up in the pubsub client while the resync runs; their slots
+    // decide whether they are newer than the RPC read.
+    resync(client, wallets, concurrency, format, last_seen).await?;
+
+    let mut received = false;
+    let mut notifications = stream::select_all(subscriptions);
+    while let Some((wallet, response)) = notifications.next().await {
+        received = true;
+        let seen = Seen { lamports: response.value.lamports, slot: response.context.slot };
+        record(&mut io::stdout().lock(), wallet, seen, format, last_seen)?;
+    }
+    drop(notifications);
+
+    pubsub.shutdown().await?;
+    Ok(received)
+}
+
+async fn resync(
+    client: &Arc<RpcClient>,
+    wallets: &[Pubkey],
+    concurrency: usize,
+    format: OutputFormat,
+    last_seen: &mut HashMap<Pubkey, Seen>,
+) -> Result<(), Box<dyn Error>> {
+    for report in scanner::scan_balances(Arc::clone(client), wallets, concurrency).await {
+        match report.result {
+            Ok(balance) => {
+                let seen = Seen { lamports: balance.lamports, slot: balance.slot };
+                record(&mut io::stdout().lock(), report.wallet, seen, format, last_seen)?;
+            }
+            Err(e) => eprintln!("Error for wallet {}: {}", report.wallet, e),
+        }
+    }
+    Ok(())
+}
+
+/// Prints a change when `seen` differs from the last known balance. The first sighting
+/// only sets the baseline, and a value older than the known one is ignored.
+fn record(
+    out: &mut impl Write,
+    wallet: Pubkey,
+    seen: Seen,
+    format: OutputFormat,
+    last_seen: &mut HashMap<Pubkey, Seen>,
+) -> io::Result<()> {
+    let previous = match last_seen.get(&wallet) {
+        Some(previous) if seen.slot < previous.slot => return Ok(()),
+        previous => previous.copied(),
+    };
+    last_seen.insert(wallet, seen);
+    match previous {
+        Some(previous) if previous.lamports != seen.lamports => {
+            let change = BalanceChange { wallet, slot: seen.slot, previous: previous.lamports, lamports: seen.lamports };
+            output::write_change(out, format, &change)?;
+            out.flush()
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_prints_only_changes() {
+        let wallet = Pubkey::new_unique();
+        let mut last_seen = HashMap::new();
+        let mut record_at = |lamports, slot| {
+            let mut out = Vec::new();
+            record(&mut out, wallet, Seen { lamports, slot }, OutputFormat::Table, &mut last_seen).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(record_at(1_000, 10), "", "first sighting only sets the baseline");
+        assert_eq!(record_at(1_000, 11), "", "unchanged lamports");
+        assert_eq!(record_at(400, 12), format!("slot 12: wallet {} balance 1000 -> 400 lamports (-600)\n", wallet));
+        assert_eq!(record_at(900, 13), format!("slot 13: wallet {} balance 400 -> 900 lamports (+500)\n", wallet));
+        // A notification queued before the resync read is older and must not undo it.
+        assert_eq!(record_at(400, 12), "");
+        assert_eq!(last_seen[&wallet], Seen { lamports: 900, slot: 13 });
+    }
+
+    #[test]
+    fn backoff_caps_and_resets_after_data() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.next(false).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff.next(true), INITIAL_RECONNECT_DELAY);
+        assert_eq!(backoff.next(false), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,239 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::{load::LoadPlan, simulate::Simulation};
+
+/// Бенчмарк SOL-переводов: отправляет транзакции и измеряет задержки.
+#[derive(Debug, Parser)]
+#[command(name = "task2", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Путь к YAML-конфигу с кошельками и суммой перевода.
+    #[arg(short, long, default_value = "task2/config.yaml")]
+    pub config: PathBuf,
+
+    /// RPC URL кластера.
+    #[arg(short = 'u', long = "url", default_value = "http://127.0.0.1:8899")]
+    pub rpc_url: String,
+
+    /// Максимальное число транзакций в обработке одновременно.
+    #[arg(long, default_value_t = 5, value_parser = parse_limit)]
+    pub concurrency: usize,
+
+    /// Уровень подтверждения, которого ждёт бенчмарк.
+    #[arg(long, value_enum, default_value_t = Commitment::Finalized)]
+    pub commitment: Commitment,
+
+    /// Режим нагрузки: держать заданный TPS вместо одного перевода на пару кошельков.
+    /// Нужен вместе с `--duration` или `--count`.
+    #[arg(long, value_name = "TPS")]
+    pub target_tps: Option<f64>,
+
+    /// Длительность нагрузки, например `60s` или `5m`.
+    #[arg(long, value_parser = humantime::parse_duration, requires = "target_tps", conflicts_with = "count")]
+    pub duration: Option<Duration>,
+
+    /// Общее число транзакций в режиме нагрузки.
+    #[arg(long, requires = "target_tps")]
+    pub count: Option<usize>,
+
+    /// Время линейного разгона до целевого TPS.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+    pub ramp_up: Duration,
+
+    /// Куда отправлять транзакции: через RPC-узел или напрямую лидерам по QUIC.
+    #[arg(long, value_enum, default_value_t = SendMode::Rpc)]
+    pub send_mode: SendMode,
+
+    /// Чем подписывать транзакции: недавним блокхэшем, общим кэшем блокхэша или
+    /// durable nonce отправителя. Чтобы сравнить задержки и долю попадания nonce и
+    /// недавнего блокхэша, сохраните два прогона с одним конфигом через `--json`
+    /// (`--blockhash recent` и `--blockhash nonce`) и передайте их в `task2 compare`.
+    #[arg(long, value_enum, default_value_t = BlockhashSource::Recent)]
+    pub blockhash: BlockhashSource,
+
+    /// Как часто обновлять кэш блокхэша в режиме `--blockhash cached`.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "400ms")]
+    pub blockhash_refresh: Duration,
+
+    /// Симулировать каждую транзакцию перед отправкой и записать в отчёт комиссию и CU;
+    /// транзакции с ошибкой симуляции не отправляются.
+    #[arg(long)]
+    pub simulate: bool,
+
+    /// Только симуляция и оценка комиссии: ничего не отправляется, отчёт той же структуры.
+    #[arg(long, conflicts_with = "simulate")]
+    pub dry_run: bool,
+
+    /// Лимит транзакций в полёте в режиме нагрузки; слоты сверх него отбрасываются.
+    #[arg(long, default_value_t = 1000, value_parser = parse_limit)]
+    pub max_in_flight: usize,
+
+    /// Сохранить отчёт о прогоне в JSON.
+    #[arg(long, value_name = "PATH")]
+    pub json: Option<PathBuf>,
+
+    /// Сохранить отчёт о прогоне в CSV.
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Сравнить два JSON-отчёта, сохранённых через `--json`, например прогоны
+    /// с `--blockhash recent` и `--blockhash nonce`.
+    Compare {
+        /// Базовый отчёт.
+        baseline: PathBuf,
+        /// Отчёт, который сравнивается с базовым.
+        candidate: PathBuf,
+    },
+    /// Создать кошельки для бенчмарка и пополнить их.
+    Setup {
+        /// Каталог с файлами ключей; его же указывает `wallets_dir` в конфиге.
+        #[arg(long, default_value = "task2/wallets")]
+        dir: PathBuf,
+        /// Сколько кошельков должно быть в каталоге; недостающие создаются.
+        #[arg(short = 'n', long)]
+        count: usize,
+        /// Баланс, до которого пополняется каждый кошелёк, в лампортах.
+        #[arg(long, default_value_t = 100_000_000)]
+        lamports: u64,
+        /// Мастер-кошелёк, с которого идут пополнения.
+        #[arg(long, value_name = "KEYPAIR", required_unless_present = "airdrop")]
+        funder: Option<PathBuf>,
+        /// Пополнить через requestAirdrop вместо мастер-кошелька (localnet, devnet).
+        #[arg(long, conflicts_with = "funder")]
+        airdrop: bool,
+        /// RPC URL кластера.
+        #[arg(short = 'u', long = "url", default_value = "http://127.0.0.1:8899")]
+        rpc_url: String,
+    },
+    /// Закрыть nonce-аккаунты и вернуть остатки со всех кошельков каталога.
+    Teardown {
+        /// Каталог с файлами ключей.
+        #[arg(long, default_value = "task2/wallets")]
+        dir: PathBuf,
+        /// Получатель остатков, обычно мастер-кошелёк.
+        #[arg(long, value_name = "PUBKEY")]
+        to: Pubkey,
+        /// RPC URL кластера.
+        #[arg(short = 'u', long = "url", default_value = "http://127.0.0.1:8899")]
+        rpc_url: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SendMode {
+    /// `sendTransaction` через RPC-узел.
+    Rpc,
+    /// TPU-клиент: QUIC-соединения с текущим и ближайшими лидерами.
+    Tpu,
+}
+
+impl SendMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            SendMode::Rpc => "rpc",
+            SendMode::Tpu => "tpu",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BlockhashSource {
+    /// `getLatestBlockhash` перед каждой транзакцией.
+    Recent,
+    /// Общий для всех задач блокхэш, который фоновая задача обновляет раз в
+    /// `--blockhash-refresh`; в отчёт пишется его возраст на момент подписи.
+    Cached,
+    /// Durable nonce: у каждого отправителя свой nonce-аккаунт, транзакция начинается
+    /// с `advance_nonce_account` и не истекает по высоте блоков. Отправитель держит
+    /// в полёте не больше одной транзакции. Nonce-аккаунты кошельков из `wallets_dir`
+    /// остаются до `task2 teardown`, а отправителей из `from_wallets` закрываются в конце прогона.
+    Nonce,
+}
+
+impl BlockhashSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockhashSource::Recent => "recent",
+            BlockhashSource::Cached => "cached",
+            BlockhashSource::Nonce => "nonce",
+        }
+    }
+}
+
+impl From<Commitment> for CommitmentConfig {
+    fn from(commitment: Commitment) -> Self {
+        match commitment {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+impl Cli {
+    pub fn simulation(&self) -> Simulation {
+        match (self.dry_run, self.simulate) {
+            (true, _) => Simulation::DryRun,
+            (false, true) => Simulation::BeforeSend,
+            (false, false) => Simulation::Off,
+        }
+    }
+
+    /// План нагрузки, если включён режим `--target-tps`.
+    pub fn load_plan(&self) -> Result<Option<LoadPlan>, String> {
+        let Some(target_tps) = self.target_tps else {
+            return Ok(None);
+        };
+        if !(target_tps > 0.0 && target_tps.is_finite()) {
+            return Err("--target-tps должен быть положительным числом".to_string());
+        }
+        if self.duration.is_none() && self.count.is_none() {
+            return Err("для --target-tps укажите --duration или --count".to_string());
+        }
+        Ok(Some(LoadPlan {
+            target_tps,
+            duration: self.duration,
+            count: self.count,
+            ramp_up: self.ramp_up,
+            max_in_flight: self.max_in_flight,
+        }))
+    }
+}
+
+/// Лимит параллельности: с нулём прогон ждал бы вечно или отбрасывал бы все слоты.
+fn parse_limit(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("значение должно быть не меньше 1".to_string()),
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_concurrency_limits_are_rejected() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["task2"], args].concat());
+        assert!(parse(&["--concurrency", "0"]).is_err());
+        assert!(parse(&["--max-in-flight", "0"]).is_err());
+        let cli = parse(&["--concurrency", "1", "--max-in-flight", "1"]).unwrap();
+        assert_eq!((cli.concurrency, cli.max_in_flight), (1, 1));
+    }
+}
@@ -0,0 +1,633 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcSignatureSubscribeConfig,
+    rpc_response::RpcSignatureResult,
+};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    signature::Signature,
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::resend::Expiry;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Максимум подписей в одном запросе `getSignatureStatuses`.
+const MAX_SIGNATURES_PER_REQUEST: usize = 256;
+
+const LEVELS: [CommitmentLevel; 3] = [
+    CommitmentLevel::Processed,
+    CommitmentLevel::Confirmed,
+    CommitmentLevel::Finalized,
+];
+
+/// Способ отслеживания подтверждений, выбирается полем `tracker` в конфиге.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackerKind {
+    /// Отдельный опрос `getSignatureStatuses` для каждой транзакции.
+    #[default]
+    Poll,
+    /// Общий опрос всех ожидающих подписей пачками до 256 штук.
+    Batch,
+    /// Подписки PubSub `signatureSubscribe` на каждый уровень подтверждения.
+    Websocket,
+}
+
+impl TrackerKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            TrackerKind::Poll => "poll",
+            TrackerKind::Batch => "batch",
+            TrackerKind::Websocket => "websocket",
+        }
+    }
+}
+
+/// Время от окончания отправки до каждого уровня подтверждения;
+/// `None`, если уровень не был замечен до таймаута.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimes {
+    pub processed: Option<Duration>,
+    pub confirmed: Option<Duration>,
+    pub finalized: Option<Duration>,
+}
+
+/// Чем закончилась отправка транзакции.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Достигла целевого уровня и выполнилась без ошибки.
+    Landed,
+    /// Попала в блок, но выполнилась с ошибкой.
+    Failed,
+    /// Блокхэш истёк раньше, чем транзакция попала в блок.
+    Expired,
+    /// Транзакция не была отправлена: не удалось её собрать, или RPC отклонил
+    /// отправку, например на предварительной симуляции.
+    Rejected,
+    /// Целевой уровень не был достигнут до таймаута.
+    TimedOut,
+    /// Транзакция прошла симуляцию в режиме `--dry-run` и не отправлялась.
+    Simulated,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 6] = [
+        Outcome::Landed,
+        Outcome::Failed,
+        Outcome::Expired,
+        Outcome::Rejected,
+        Outcome::TimedOut,
+        Outcome::Simulated,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Landed => "landed",
+            Outcome::Failed => "failed",
+            Outcome::Expired => "expired",
+            Outcome::Rejected => "rejected",
+            Outcome::TimedOut => "timed_out",
+            Outcome::Simulated => "simulated",
+        }
+    }
+
+    /// Дошла ли транзакция до сети: отклонённые до отправки и просимулированные не дошли.
+    pub fn reached_network(self) -> bool {
+        !matches!(self, Outcome::Rejected | Outcome::Simulated)
+    }
+}
+
+/// Итог ожидания одной транзакции.
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    pub stages: StageTimes,
+    /// Время до целевого уровня, либо до окончания ожидания.
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+    /// Ошибка выполнения транзакции в блоке.
+    pub error: Option<String>,
+}
+
+/// Моменты, когда трекер заметил каждый уровень подтверждения.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Observed {
+    stages: [Option<Instant>; 3],
+    error: Option<String>,
+    /// Блокхэш истёк, а транзакция так и не попала в блок.
+    expired: bool,
+}
+
+impl Observed {
+    fn index(level: CommitmentLevel) -> usize {
+        match level {
+            CommitmentLevel::Processed => 0,
+            CommitmentLevel::Confirmed => 1,
+            CommitmentLevel::Finalized => 2,
+        }
+    }
+
+    /// Отмечает уровень `level` и все более слабые уровни: опрос может их пропустить,
+    /// но транзакция прошла их не позже, чем был замечен `level`.
+    fn observe(&mut self, level: CommitmentLevel, at: Instant) {
+        for stage in &mut self.stages[..=Self::index(level)] {
+            stage.get_or_insert(at);
+        }
+    }
+
+    fn reached(&self, level: CommitmentLevel) -> bool {
+        self.stages[Self::index(level)].is_some()
+    }
+
+    /// Учитывает ответ `getSignatureStatuses`: достигнутый уровень и текущую ошибку выполнения.
+    fn record_status(&mut self, level: CommitmentLevel, error: Option<String>, at: Instant) {
+        self.observe(level, at);
+        self.error = error;
+    }
+
+    /// Учитывает уведомление подписки на уровень `level`; ошибка из прошлых уведомлений сохраняется.
+    fn notify(&mut self, level: CommitmentLevel, (at, error): Notification) {
+        self.observe(level, at);
+        self.error = self.error.take().or(error);
+    }
+
+    /// Отмечает истечение блокхэша, если транзакция ещё не замечена ни на одном уровне.
+    fn check_expiry(&mut self, expiry: &Expiry) -> bool {
+        self.expired |= !self.reached(CommitmentLevel::Processed) && expiry.is_expired();
+        self.expired
+    }
+
+    fn into_confirmation(self, sent_at: Instant, target: CommitmentLevel) -> Confirmation {
+        let since_send = |stage: Option<Instant>| stage.map(|at| at.saturating_duration_since(sent_at));
+        let [processed, confirmed, finalized] = self.stages.map(since_send);
+        let stages = StageTimes { processed, confirmed, finalized };
+        let reached = since_send(self.stages[Self::index(target)]);
+        let outcome = match (&self.error, reached) {
+            (Some(_), _) => Outcome::Failed,
+            (None, Some(_)) => Outcome::Landed,
+            (None, None) if self.expired => Outcome::Expired,
+            (None, None) => Outcome::TimedOut,
+        };
+        Confirmation {
+            stages,
+            elapsed: reached.unwrap_or_else(|| sent_at.elapsed()),
+            outcome,
+            error: self.error,
+        }
+    }
+}
+
+/// Самый сильный уровень, которого достиг статус подписи (`satisfies` — его `satisfies_commitment`).
+fn status_level(satisfies: impl Fn(CommitmentConfig) -> bool) -> CommitmentLevel {
+    [CommitmentConfig::finalized(), CommitmentConfig::confirmed()]
+        .into_iter()
+        .find(|c| satisfies(*c))
+        .unwrap_or_else(CommitmentConfig::processed)
+        .commitment
+}
+
+/// Запрос фоновому опросу на отслеживание подписи.
+struct Registration {
+    signature: Signature,
+    target: CommitmentLevel,
+    timeout: Duration,
+    expiry: Expiry,
+    /// Когда закончилась отправка: от этого момента считается таймаут. Задаётся в `Watch::wait`.
+    sent_at: Arc<OnceLock<Instant>>,
+    done: oneshot::Sender<Observed>,
+}
+
+/// Отслеживает подтверждения выбранным способом.
+pub struct Tracker(Backend);
+
+enum Backend {
+    Poll {
+        client: Arc<RpcClient>,
+    },
+    Batch {
+        register: mpsc::UnboundedSender<Registration>,
+    },
+    Websocket {
+        pubsub: Arc<PubsubClient>,
+        client: Arc<RpcClient>,
+    },
+}
+
+/// Ожидание одной подписи, начатое до отправки транзакции.
+pub struct Watch {
+    signature: Signature,
+    target: CommitmentLevel,
+    timeout: Duration,
+    expiry: Expiry,
+    kind: WatchKind,
+}
+
+/// Время уведомления и ошибка выполнения транзакции, если она была.
+type Notification = (Instant, Option<String>);
+
+enum WatchKind {
+    Poll(Arc<RpcClient>),
+    Batch(oneshot::Receiver<Observed>, Arc<OnceLock<Instant>>),
+    Websocket(Vec<(CommitmentLevel, oneshot::Receiver<Notification>)>),
+}
+
+impl Tracker {
+    pub async fn new(kind: TrackerKind, client: Arc<RpcClient>, ws_url: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Tracker(match kind {
+            TrackerKind::Poll => Backend::Poll { client },
+            TrackerKind::Batch => {
+                let (register, requests) = mpsc::unbounded_channel();
+                tokio::spawn(run_batch_poller(client, requests));
+                Backend::Batch { register }
+            }
+            TrackerKind::Websocket => {
+                let pubsub = PubsubClient::new(ws_url)
+                    .await
+                    .map_err(|e| format!("Не удалось подключиться к {}: {}", ws_url, e))?;
+                Backend::Websocket { pubsub: Arc::new(pubsub), client }
+            }
+        }))
+    }
+
+    /// Начинает следить за подписью. Вызывается до отправки транзакции, чтобы
+    /// подписка PubSub успела установиться и не пропустила уведомления. Ожидание
+    /// заканчивается по `timeout` или когда истекает блокхэш ещё не замеченной транзакции.
+    pub async fn watch(
+        &self,
+        signature: Signature,
+        target: CommitmentConfig,
+        timeout: Duration,
+        expiry: Expiry,
+    ) -> Watch {
+        let target = target.commitment;
+        let kind = match &self.0 {
+            Backend::Poll { client } => WatchKind::Poll(Arc::clone(client)),
+            Backend::Batch { register } => {
+                let (done, observed) = oneshot::channel();
+                let sent_at = Arc::new(OnceLock::new());
+                let registration =
+                    Registration { signature, target, timeout, expiry: expiry.clone(), sent_at: Arc::clone(&sent_at), done };
+                let _ = register.send(registration);
+                WatchKind::Batch(observed, sent_at)
+            }
+            Backend::Websocket { pubsub, client } => match subscribe(pubsub, signature, target).await {
+                Ok(levels) => WatchKind::Websocket(levels),
+                Err(e) => {
+                    eprintln!("Подписка на {} не удалась, переход на опрос: {}", signature, e);
+                    WatchKind::Poll(Arc::clone(client))
+                }
+            },
+        };
+        Watch { signature, target, timeout, expiry, kind }
+    }
+}
+
+impl Watch {
+    /// Дожидается целевого уровня; стадии и таймаут отсчитываются от `sent_at`.
+    pub async fn wait(self, sent_at: Instant) -> Confirmation {
+        let deadline = tokio::time::Instant::from_std(sent_at + self.timeout);
+        let observed = match self.kind {
+            WatchKind::Poll(client) => {
+                poll_signature(&client, &self.signature, self.target, deadline, &self.expiry).await
+            }
+            WatchKind::Batch(observed, started) => {
+                let _ = started.set(sent_at);
+                observed.await.unwrap_or_default()
+            }
+            WatchKind::Websocket(levels) => wait_notifications(levels, self.target, deadline, self.expiry).await,
+        };
+        observed.into_confirmation(sent_at, self.target)
+    }
+}
+
+/// Опрашивает статус одной подписи, пока она не достигнет уровня `target`.
+async fn poll_signature(
+    client: &RpcClient,
+    signature: &Signature,
+    target: CommitmentLevel,
+    deadline: tokio::time::Instant,
+    expiry: &Expiry,
+) -> Observed {
+    let mut observed = Observed::default();
+    while tokio::time::Instant::now() <= deadline {
+        if let Ok(statuses) = client.get_signature_statuses(&[*signature]).await {
+            if let Some(Some(status)) = statuses.value.first() {
+                let level = status_level(|c| status.satisfies_commitment(c));
+                observed.record_status(level, status.err.as_ref().map(ToString::to_string), Instant::now());
+                if observed.reached(target) {
+                    break;
+                }
+            }
+        }
+        if observed.check_expiry(expiry) {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    observed
+}
+
+struct Pending {
+    target: CommitmentLevel,
+    timeout: Duration,
+    expiry: Expiry,
+    observed: Observed,
+    /// Одинаковые транзакции дают одинаковые подписи, поэтому ожидающих может быть несколько.
+    done: Vec<oneshot::Sender<Observed>>,
+    sent_at: Vec<Arc<OnceLock<Instant>>>,
+}
+
+impl Pending {
+    fn add(pending: &mut HashMap<Signature, Pending>, registration: Registration) {
+        let entry = pending.entry(registration.signature).or_insert_with(|| Pending {
+            target: registration.target,
+            timeout: registration.timeout,
+            expiry: registration.expiry,
+            observed: Observed::default(),
+            done: Vec::new(),
+            sent_at: Vec::new(),
+        });
+        entry.done.push(registration.done);
+        entry.sent_at.push(registration.sent_at);
+    }
+
+    /// Таймаут, как и у остальных трекеров, отсчитывается от окончания отправки — самой
+    /// ранней из отправленных копий подписи. До отправки подпись ждёт без таймаута.
+    fn is_finished(&mut self) -> bool {
+        let first_sent = self.sent_at.iter().filter_map(|sent_at| sent_at.get()).min();
+        self.observed.reached(self.target)
+            || self.observed.check_expiry(&self.expiry)
+            || first_sent.is_some_and(|sent_at| sent_at.elapsed() > self.timeout)
+            || self.done.iter().all(|done| done.is_closed())
+    }
+}
+
+/// Фоновый опрос всех ожидающих подписей: раз в `POLL_INTERVAL` запрашивает
+/// их статусы пачками по `MAX_SIGNATURES_PER_REQUEST`. Подпись снимается с опроса,
+/// когда достигает целевого уровня, истекает её блокхэш или таймаут.
+async fn run_batch_poller(client: Arc<RpcClient>, mut requests: mpsc::UnboundedReceiver<Registration>) {
+    let mut pending: HashMap<Signature, Pending> = HashMap::new();
+    loop {
+        if pending.is_empty() {
+            match requests.recv().await {
+                Some(registration) => Pending::add(&mut pending, registration),
+                None => return,
+            }
+        }
+        while let Ok(registration) = requests.try_recv() {
+            Pending::add(&mut pending, registration);
+        }
+
+        let signatures: Vec<Signature> = pending.keys().copied().collect();
+        for chunk in signatures.chunks(MAX_SIGNATURES_PER_REQUEST) {
+            let Ok(statuses) = client.get_signature_statuses(chunk).await else { continue };
+            let now = Instant::now();
+            for (signature, status) in chunk.iter().zip(statuses.value) {
+                let (Some(status), Some(entry)) = (status, pending.get_mut(signature)) else { continue };
+                let level = status_level(|c| status.satisfies_commitment(c));
+                entry.observed.record_status(level, status.err.as_ref().map(ToString::to_string), now);
+            }
+        }
+
+        pending.retain(|_, entry| {
+            if !entry.is_finished() {
+                return true;
+            }
+            for done in entry.done.drain(..) {
+                let _ = done.send(entry.observed.clone());
+            }
+            false
+        });
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Открывает по подписке `signatureSubscribe` на каждый уровень до `target` включительно
+/// и возвращает каналы, в которые придёт время уведомления и успех транзакции.
+/// Ждёт уведомлений всех уровней сразу: пропущенное или запоздавшее уведомление о более
+/// слабом уровне не задерживает более сильные, а `Observed` заполняет пропущенные стадии.
+async fn wait_notifications(
+    levels: Vec<(CommitmentLevel, oneshot::Receiver<Notification>)>,
+    target: CommitmentLevel,
+    deadline: tokio::time::Instant,
+    expiry: Expiry,
+) -> Observed {
+    let mut observed = Observed::default();
+    let mut notifications: FuturesUnordered<_> = levels
+        .into_iter()
+        .map(|(level, notification)| notification.map(move |notification| (level, notification)))
+        .collect();
+    let expired = expiry.expired();
+    tokio::pin!(expired);
+    while !observed.reached(target) {
+        tokio::select! {
+            next = notifications.next() => match next {
+                // Подписка закрылась без уведомления: ждём остальные уровни.
+                Some((level, Ok(notification))) => observed.notify(level, notification),
+                Some((_, Err(_))) => {}
+                None => break,
+            },
+            _ = tokio::time::sleep_until(deadline) => break,
+            // Истечение блокхэша важно только до первого уведомления.
+            _ = &mut expired, if !observed.reached(CommitmentLevel::Processed) => {
+                observed.expired = true;
+                break;
+            }
+        }
+    }
+    observed
+}
+
+async fn subscribe(
+    pubsub: &Arc<PubsubClient>,
+    signature: Signature,
+    target: CommitmentLevel,
+) -> Result<Vec<(CommitmentLevel, oneshot::Receiver<Notification>)>, String> {
+    let mut levels = Vec::new();
+    for level in LEVELS.into_iter().take(Observed::index(target) + 1) {
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (mut done_tx, done_rx) = oneshot::channel();
+        let pubsub = Arc::clone(pubsub);
+        tokio::spawn(async move {
+            let config = RpcSignatureSubscribeConfig {
+                commitment: Some(CommitmentConfig { commitment: level }),
+                enable_received_notification: Some(false),
+            };
+            let (mut notifications, unsubscribe) = match pubsub.signature_subscribe(&signature, Some(config)).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            tokio::select! {
+                Some(response) = notifications.next() => {
+                    if let RpcSignatureResult::ProcessedSignature(result) = response.value {
+                        let _ = done_tx.send((Instant::now(), result.err.map(|e| e.to_string())));
+                    }
+                }
+                // Ожидающий сдался по таймауту.
+                _ = done_tx.closed() => {}
+            }
+            drop(notifications);
+            unsubscribe().await;
+        });
+        ready_rx.await.map_err(|_| "подписка прервана".to_string())??;
+        levels.push((level, done_rx));
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resend::BlockHeights;
+
+    #[test]
+    fn observing_a_level_backfills_skipped_stages() {
+        let sent_at = Instant::now();
+        let at = |ms| sent_at + Duration::from_millis(ms);
+        let mut observed = Observed::default();
+        observed.observe(CommitmentLevel::Processed, at(100));
+        observed.observe(CommitmentLevel::Finalized, at(900));
+        observed.observe(CommitmentLevel::Finalized, at(1_000));
+
+        let confirmation = observed.into_confirmation(sent_at, CommitmentLevel::Finalized);
+        assert_eq!(
+            confirmation.stages,
+            StageTimes {
+                processed: Some(Duration::from_millis(100)),
+                confirmed: Some(Duration::from_millis(900)),
+                finalized: Some(Duration::from_millis(900)),
+            }
+        );
+        assert_eq!(confirmation.elapsed, Duration::from_millis(900));
+        assert_eq!(confirmation.outcome, Outcome::Landed);
+    }
+
+    #[test]
+    fn outcomes_distinguish_failed_expired_and_timed_out() {
+        let sent_at = Instant::now();
+        let outcome = |observed: Observed| observed.into_confirmation(sent_at, CommitmentLevel::Confirmed).outcome;
+
+        let mut failed = Observed { error: Some("InsufficientFundsForRent".to_string()), ..Default::default() };
+        failed.observe(CommitmentLevel::Processed, sent_at);
+        assert_eq!(outcome(failed), Outcome::Failed);
+        assert_eq!(outcome(Observed { expired: true, ..Default::default() }), Outcome::Expired);
+
+        let mut processed = Observed::default();
+        processed.observe(CommitmentLevel::Processed, sent_at);
+        assert_eq!(outcome(processed), Outcome::TimedOut);
+    }
+
+    #[test]
+    fn batch_statuses_advance_observed_levels() {
+        let sent_at = Instant::now();
+        let at = |ms| sent_at + Duration::from_millis(ms);
+        // Статус с подтверждениями, но не укоренённый: confirmed, но не finalized.
+        let confirmed = |c: CommitmentConfig| !c.is_finalized();
+        assert_eq!(status_level(confirmed), CommitmentLevel::Confirmed);
+        assert_eq!(status_level(|c: CommitmentConfig| c.is_processed()), CommitmentLevel::Processed);
+        assert_eq!(status_level(|_| true), CommitmentLevel::Finalized);
+
+        let mut observed = Observed::default();
+        observed.record_status(CommitmentLevel::Processed, None, at(100));
+        observed.record_status(status_level(confirmed), None, at(500));
+        assert!(observed.reached(CommitmentLevel::Confirmed) && !observed.reached(CommitmentLevel::Finalized));
+        let landed = observed.clone().into_confirmation(sent_at, CommitmentLevel::Confirmed);
+        assert_eq!(landed.outcome, Outcome::Landed);
+        assert_eq!(landed.elapsed, Duration::from_millis(500));
+
+        // Ошибка выполнения берётся из последнего статуса.
+        observed.record_status(CommitmentLevel::Finalized, Some("InstructionError".to_string()), at(900));
+        let failed = observed.into_confirmation(sent_at, CommitmentLevel::Finalized);
+        assert_eq!(failed.outcome, Outcome::Failed);
+        assert_eq!(failed.error.as_deref(), Some("InstructionError"));
+    }
+
+    #[test]
+    fn websocket_notifications_keep_the_first_error() {
+        let sent_at = Instant::now();
+        let at = |ms| sent_at + Duration::from_millis(ms);
+        let mut observed = Observed::default();
+        observed.notify(CommitmentLevel::Processed, (at(50), Some("InsufficientFunds".to_string())));
+        observed.notify(CommitmentLevel::Confirmed, (at(400), None));
+
+        let confirmation = observed.into_confirmation(sent_at, CommitmentLevel::Confirmed);
+        assert_eq!(confirmation.stages.processed, Some(Duration::from_millis(50)));
+        assert_eq!(confirmation.stages.confirmed, Some(Duration::from_millis(400)));
+        assert_eq!(confirmation.outcome, Outcome::Failed);
+        assert_eq!(confirmation.error.as_deref(), Some("InsufficientFunds"));
+    }
+
+    #[tokio::test]
+    async fn websocket_wait_does_not_block_on_a_missed_processed_notification() {
+        let (_heights, block_heights) = BlockHeights::fixed(100);
+        let sent_at = Instant::now();
+        // Уведомление processed так и не приходит, а confirmed уже пришло.
+        let (_processed_tx, processed) = oneshot::channel();
+        let (confirmed_tx, confirmed) = oneshot::channel();
+        confirmed_tx.send((sent_at + Duration::from_millis(400), None)).unwrap();
+        let levels = vec![(CommitmentLevel::Processed, processed), (CommitmentLevel::Confirmed, confirmed)];
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        let wait = wait_notifications(levels, CommitmentLevel::Confirmed, deadline, block_heights.expiry(150));
+        let observed = tokio::time::timeout(Duration::from_secs(5), wait).await.unwrap();
+
+        let confirmation = observed.into_confirmation(sent_at, CommitmentLevel::Confirmed);
+        assert_eq!(confirmation.outcome, Outcome::Landed);
+        assert_eq!(confirmation.stages.processed, Some(Duration::from_millis(400)));
+        assert_eq!(confirmation.stages.confirmed, Some(Duration::from_millis(400)));
+    }
+
+    #[test]
+    fn batch_timeout_is_measured_from_send() {
+        let (_heights, block_heights) = BlockHeights::fixed(100);
+        let registration = |signature| {
+            let (done, observed) = oneshot::channel();
+            let sent_at = Arc::new(OnceLock::new());
+            let registration = Registration {
+                signature,
+                target: CommitmentLevel::Finalized,
+                timeout: Duration::from_secs(1),
+                expiry: block_heights.expiry(150),
+                sent_at: Arc::clone(&sent_at),
+                done,
+            };
+            (registration, sent_at, observed)
+        };
+        let (stale, fresh) = (Signature::new_unique(), Signature::new_unique());
+        let mut pending = HashMap::new();
+        let (early, early_sent, _early_rx) = registration(stale);
+        Pending::add(&mut pending, early);
+        let (now, now_sent, _now_rx) = registration(fresh);
+        Pending::add(&mut pending, now);
+
+        // Пока отправка не закончилась, таймаут не идёт.
+        assert!(!pending.get_mut(&stale).unwrap().is_finished());
+        early_sent.set(Instant::now() - Duration::from_secs(2)).unwrap();
+        now_sent.set(Instant::now()).unwrap();
+        assert!(pending.get_mut(&stale).unwrap().is_finished());
+        assert!(!pending.get_mut(&fresh).unwrap().is_finished());
+
+        // Повторная отправка той же подписи не сдвигает отсчёт от первой.
+        let (again, again_sent, _again_rx) = registration(stale);
+        Pending::add(&mut pending, again);
+        again_sent.set(Instant::now()).unwrap();
+        let entry = pending.get_mut(&stale).unwrap();
+        assert_eq!(entry.done.len(), 2);
+        assert!(entry.is_finished());
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+
+/// Почему транзакция не дошла до целевого уровня подтверждения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxError {
+    /// Не удалось прочитать файл ключей отправителя, транзакция не собиралась.
+    Keypair(String),
+    /// Не удалось прочитать nonce отправителя.
+    Nonce(String),
+    /// Не удалось получить недавний блокхэш.
+    Blockhash(String),
+    /// Не удалось собрать или подписать транзакцию.
+    Build(String),
+    /// RPC-узел отклонил транзакцию на предварительной симуляции.
+    Simulation(String),
+    /// Транзакцию не удалось отправить: ошибка сети, RPC или TPU.
+    Send(String),
+    /// RPC-узел не ответил на служебный запрос о транзакции, например на симуляцию.
+    Rpc(String),
+    /// Транзакция попала в блок, но выполнилась с ошибкой.
+    Execution(String),
+    /// Блокхэш истёк раньше, чем транзакция попала в блок.
+    Expired,
+    /// Целевой уровень не был достигнут до таймаута.
+    Timeout,
+}
+
+impl TxError {
+    /// Тип ошибки для отчёта.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TxError::Keypair(_) => "keypair",
+            TxError::Nonce(_) => "nonce",
+            TxError::Blockhash(_) => "blockhash",
+            TxError::Build(_) => "build",
+            TxError::Simulation(_) => "simulation",
+            TxError::Send(_) => "send",
+            TxError::Rpc(_) => "rpc",
+            TxError::Execution(_) => "execution",
+            TxError::Expired => "expired",
+            TxError::Timeout => "timeout",
+        }
+    }
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::Keypair(e)
+            | TxError::Nonce(e)
+            | TxError::Blockhash(e)
+            | TxError::Build(e)
+            | TxError::Simulation(e)
+            | TxError::Send(e)
+            | TxError::Rpc(e)
+            | TxError::Execution(e) => f.write_str(e),
+            TxError::Expired => f.write_str("блокхэш истёк до попадания в блок"),
+            TxError::Timeout => f.write_str("целевой уровень не достигнут до таймаута"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_error_has_its_own_kind() {
+        let message = || "ошибка".to_string();
+        let errors = [
+            TxError::Keypair(message()),
+            TxError::Nonce(message()),
+            TxError::Blockhash(message()),
+            TxError::Build(message()),
+            TxError::Simulation(message()),
+            TxError::Send(message()),
+            TxError::Rpc(message()),
+            TxError::Execution(message()),
+            TxError::Expired,
+            TxError::Timeout,
+        ];
+        let kinds: Vec<&str> = errors.iter().map(TxError::kind).collect();
+        assert_eq!(
+            kinds,
+            ["keypair", "nonce", "blockhash", "build", "simulation", "send", "rpc", "execution", "expired", "timeout"]
+        );
+        assert_eq!(TxError::Send(message()).to_string(), "ошибка");
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use serde::Deserialize;
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey};
+
+use crate::stats::RunStats;
+
+/// `getRecentPrioritizationFees` принимает не больше 128 аккаунтов.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Секция `compute_budget` конфига.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ComputeBudgetConfig {
+    /// Лимит вычислительных единиц на транзакцию (`set_compute_unit_limit`).
+    pub unit_limit: Option<u32>,
+    /// Цена вычислительной единицы в микролампортах (`set_compute_unit_price`).
+    pub unit_price: Option<u64>,
+    /// Взять цену как перцентиль `getRecentPrioritizationFees` по аккаунтам переводов.
+    /// В режиме перебора полученная цена добавляется к уровням `sweep`.
+    pub recent_fee_percentile: Option<f64>,
+    /// Уровни цены для режима перебора: бенчмарк прогоняется по разу на каждый уровень.
+    #[serde(default)]
+    pub sweep: Vec<u64>,
+}
+
+/// Инструкции бюджета, добавляемые перед переводом.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let limit = self.unit_limit.map(ComputeBudgetInstruction::set_compute_unit_limit);
+        let price = self.unit_price.map(ComputeBudgetInstruction::set_compute_unit_price);
+        limit.into_iter().chain(price).collect()
+    }
+}
+
+impl ComputeBudgetConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.unit_price.is_some() && self.recent_fee_percentile.is_some() {
+            return Err("compute_budget: укажите либо unit_price, либо recent_fee_percentile".to_string());
+        }
+        if self.unit_price.is_some() && !self.sweep.is_empty() {
+            return Err("compute_budget: unit_price не используется вместе с sweep".to_string());
+        }
+        if let Some(pct) = self.recent_fee_percentile {
+            if !(0.0..=100.0).contains(&pct) {
+                return Err("compute_budget: recent_fee_percentile должен быть от 0 до 100".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Бюджеты прогонов: один без перебора или по одному на каждый уровень `sweep`
+    /// (по возрастанию цены, без повторов). `recent_fee` — цена, полученная из
+    /// `getRecentPrioritizationFees`, если она запрашивалась.
+    pub fn budgets(&self, recent_fee: Option<u64>) -> Vec<ComputeBudget> {
+        let budget = |unit_price| ComputeBudget { unit_limit: self.unit_limit, unit_price };
+        if self.sweep.is_empty() {
+            return vec![budget(recent_fee.or(self.unit_price))];
+        }
+        let levels: BTreeSet<u64> = self.sweep.iter().copied().chain(recent_fee).collect();
+        levels.into_iter().map(|price| budget(Some(price))).collect()
+    }
+}
+
+/// Перцентиль по ближайшему рангу, как в статистике задержек; 0 для пустого списка.
+pub fn fee_percentile(fees: &[u64], pct: f64) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    let mut sorted = fees.to_vec();
+    sorted.sort_unstable();
+    let rank = (pct * sorted.len() as f64 / 100.0).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Цена вычислительной единицы по недавним блокам, в которых писались аккаунты переводов.
+pub async fn recent_prioritization_fee(
+    client: &RpcClient,
+    accounts: impl IntoIterator<Item = Pubkey>,
+    pct: f64,
+) -> Result<u64, ClientError> {
+    let accounts: Vec<Pubkey> = accounts
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .take(MAX_FEE_ACCOUNTS)
+        .collect();
+    let fees: Vec<u64> = client
+        .get_recent_prioritization_fees(&accounts)
+        .await?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(fee_percentile(&fees, pct))
+}
+
+/// Таблица режима перебора: доля подтверждённых транзакций и задержка на каждом уровне цены.
+pub fn print_sweep(levels: &[(u64, RunStats)]) {
+    let total = |stats: &RunStats, name: &str| -> String {
+        let value = stats.total.as_ref().and_then(|total| match name {
+            "mean" => Some(total.mean),
+            _ => total.percentiles.iter().find(|(n, _)| *n == name).map(|(_, d)| *d),
+        });
+        value.map(|d: Duration| format!("{:.2?}", d)).unwrap_or_else(|| "-".to_string())
+    };
+    println!("\nПеребор приоритетной комиссии (цена CU в микролампортах):");
+    println!(
+        "{:>12} {:>10} {:>10} {:>9} {:>12} {:>12} {:>12}",
+        "ЦЕНА CU", "ОТПРАВЛЕНО", "ДОШЛО", "ДОЛЯ %", "p50", "p90", "СРЕДНЕЕ"
+    );
+    for (unit_price, stats) in levels {
+        println!(
+            "{:>12} {:>10} {:>10} {:>9.2} {:>12} {:>12} {:>12}",
+            unit_price,
+            stats.throughput.submitted,
+            stats.throughput.landed,
+            stats.success_percentage(),
+            total(stats, "p50"),
+            total(stats, "p90"),
+            total(stats, "mean")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_levels_include_recent_fee_once() {
+        let config = ComputeBudgetConfig {
+            unit_limit: Some(1_400),
+            sweep: vec![10_000, 0, 1_000, 10_000],
+            ..Default::default()
+        };
+        let prices: Vec<Option<u64>> = config.budgets(Some(1_000)).iter().map(|b| b.unit_price).collect();
+        assert_eq!(prices, vec![Some(0), Some(1_000), Some(10_000)]);
+        assert_eq!(config.budgets(None)[0].instructions().len(), 2);
+
+        let fees = [0, 0, 5, 100, 20, 0, 7, 3, 1, 50];
+        assert_eq!(fee_percentile(&fees, 50.0), 3);
+        assert_eq!(fee_percentile(&fees, 90.0), 50);
+        assert_eq!(fee_percentile(&[], 75.0), 0);
+    }
+}
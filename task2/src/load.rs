@@ -0,0 +1,234 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::Semaphore,
+    task::{self, JoinError},
+    time::Instant,
+};
+
+use crate::{
+    confirmation::Outcome,
+    transfer::{Sender, Transfer},
+    TxMetrics,
+};
+
+/// Параметры режима нагрузки.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadPlan {
+    pub target_tps: f64,
+    /// Нагрузка идёт, пока не истечёт `duration` или не будет запланировано `count` транзакций.
+    pub duration: Option<Duration>,
+    pub count: Option<usize>,
+    /// Время линейного разгона от нуля до `target_tps`.
+    pub ramp_up: Duration,
+    /// Лимит транзакций в полёте; слоты сверх лимита отбрасываются.
+    pub max_in_flight: usize,
+}
+
+/// Момент отправки `index`-й транзакции от начала нагрузки. Во время разгона темп растёт
+/// линейно, поэтому к моменту `t < ramp_up` отправлено `tps * t² / (2 * ramp_up)` транзакций.
+fn scheduled_offset(index: usize, target_tps: f64, ramp_up: Duration) -> Duration {
+    let index = index as f64;
+    let ramp = ramp_up.as_secs_f64();
+    let during_ramp = target_tps * ramp / 2.0;
+    let seconds = if index < during_ramp {
+        (2.0 * ramp * index / target_tps).sqrt()
+    } else {
+        ramp + (index - during_ramp) / target_tps
+    };
+    Duration::from_secs_f64(seconds)
+}
+
+/// Итоги режима нагрузки.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadSummary {
+    pub target_tps: f64,
+    pub achieved_tps: f64,
+    /// Время от первой отправки до окончания последней, дошедшей до сети (без ожидания подтверждений).
+    pub send_window_ms: f64,
+    pub scheduled: usize,
+    /// Транзакции, которые дошли до сети: без отклонённых до отправки и просимулированных.
+    pub sent: usize,
+    /// Слоты, пропущенные из-за лимита транзакций в полёте.
+    pub dropped: usize,
+    /// Наибольшая надбавка к `amount` в лампортах: сумма растёт на 1 с каждым кругом по отправителям.
+    #[serde(default)]
+    pub amount_bump: u64,
+    /// Отправленные транзакции, не достигшие целевого уровня до таймаута.
+    pub timed_out: usize,
+    /// Задачи, завершившиеся паникой.
+    pub errors: usize,
+}
+
+impl LoadSummary {
+    pub fn drop_percentage(&self) -> f64 {
+        percentage(self.dropped, self.scheduled)
+    }
+
+    pub fn timeout_percentage(&self) -> f64 {
+        percentage(self.timed_out, self.sent)
+    }
+
+    pub fn print(&self) {
+        println!("\nРежим нагрузки за {:.2?}:", Duration::from_secs_f64(self.send_window_ms / 1000.0));
+        println!("Целевой TPS: {:.2}, достигнутый TPS: {:.2}", self.target_tps, self.achieved_tps);
+        println!(
+            "Запланировано: {}, отправлено: {}, отброшено: {} ({:.2}%)",
+            self.scheduled,
+            self.sent,
+            self.dropped,
+            self.drop_percentage()
+        );
+        if self.amount_bump > 0 {
+            println!("Сумма перевода росла с каждым кругом по отправителям: до +{} лампортов", self.amount_bump);
+        }
+        println!(
+            "Не подтверждено до таймаута: {} ({:.2}%), ошибок задач: {}",
+            self.timed_out,
+            self.timeout_percentage(),
+            self.errors
+        );
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 / total as f64 * 100.0
+}
+
+/// Открытый цикл: транзакции отправляются по расписанию независимо от того, как быстро
+/// подтверждаются предыдущие. Отправители перебираются по кругу; чтобы повтор той же пары
+/// в пределах одного блокхэша не дал ту же подпись, сумма растёт на номер круга.
+pub async fn run(
+    sender: Arc<Sender>,
+    transfers: Arc<Vec<Transfer>>,
+    amount: u64,
+    plan: LoadPlan,
+    mut on_result: impl FnMut(&TxMetrics),
+) -> (Vec<TxMetrics>, LoadSummary) {
+    let in_flight = Arc::new(Semaphore::new(plan.max_in_flight));
+    let mut tasks = FuturesUnordered::new();
+    let mut metrics = Vec::new();
+    let (mut scheduled, mut dropped, mut errors) = (0, 0, 0);
+    let mut amount_bump = 0;
+    let mut collect = |result: Result<TxMetrics, JoinError>, metrics: &mut Vec<TxMetrics>| match result {
+        Ok(tx_metrics) => {
+            on_result(&tx_metrics);
+            metrics.push(tx_metrics);
+        }
+        Err(e) => {
+            eprintln!("Ошибка задачи: {:?}", e);
+            errors += 1;
+        }
+    };
+
+    let start = Instant::now();
+    for index in 0.. {
+        if plan.count.is_some_and(|count| index >= count) {
+            break;
+        }
+        let offset = scheduled_offset(index, plan.target_tps, plan.ramp_up);
+        if plan.duration.is_some_and(|duration| offset >= duration) {
+            break;
+        }
+
+        let sleep = tokio::time::sleep_until(start + offset);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                Some(result) = tasks.next() => collect(result, &mut metrics),
+            }
+        }
+
+        scheduled += 1;
+        let Ok(permit) = Arc::clone(&in_flight).try_acquire_owned() else {
+            dropped += 1;
+            sender.progress.dropped();
+            continue;
+        };
+        // Та же пара с тем же блокхэшем и суммой дала бы ту же подпись, и кластер отбросил бы
+        // её как дубликат. Надбавка в лампортах не меняет ни размер, ни состав транзакции,
+        // так что прогоны сравнимы с замкнутым циклом и перебором комиссий.
+        let bump = (index / transfers.len()) as u64;
+        amount_bump = amount_bump.max(bump);
+        let lamports = amount + bump;
+        let (sender, transfers) = (Arc::clone(&sender), Arc::clone(&transfers));
+        tasks.push(task::spawn(async move {
+            let _permit = permit;
+            sender.transfer(&transfers[index % transfers.len()], lamports).await
+        }));
+    }
+
+    while let Some(result) = tasks.next().await {
+        collect(result, &mut metrics);
+    }
+
+    // Отправки, закончившиеся после расписания, входят и в числитель, и в окно: иначе TPS
+    // завышается как раз тогда, когда узел не успевает.
+    let sent = metrics.iter().filter(|m| m.outcome.reached_network()).count();
+    let send_window = send_window(start.into_std(), &metrics);
+    let summary = LoadSummary {
+        target_tps: plan.target_tps,
+        achieved_tps: if send_window.is_zero() { 0.0 } else { sent as f64 / send_window.as_secs_f64() },
+        send_window_ms: send_window.as_secs_f64() * 1000.0,
+        scheduled,
+        sent,
+        dropped,
+        amount_bump,
+        timed_out: metrics.iter().filter(|m| m.outcome == Outcome::TimedOut).count(),
+        errors,
+    };
+    (metrics, summary)
+}
+
+/// Окно отправки: от начала расписания до окончания последней отправки, дошедшей до сети.
+fn send_window(start: std::time::Instant, metrics: &[TxMetrics]) -> Duration {
+    metrics
+        .iter()
+        .filter(|m| m.outcome.reached_network())
+        .filter_map(|m| m.sent_at)
+        .max()
+        .map_or(Duration::ZERO, |sent_at| sent_at.saturating_duration_since(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_ramps_up_linearly_then_holds_target_rate() {
+        let offset = |i| scheduled_offset(i, 10.0, Duration::from_secs(2)).as_secs_f64();
+        // За 2 секунды разгона до 10 TPS уходит 10 транзакций, дальше по 0.1 с.
+        assert_eq!(offset(0), 0.0);
+        assert!((offset(10) - 2.0).abs() < 1e-9);
+        assert!((offset(20) - 3.0).abs() < 1e-9);
+        // Первая половина разгона медленнее второй.
+        assert!(offset(5) > 1.0);
+
+        let flat = |i| scheduled_offset(i, 4.0, Duration::ZERO).as_secs_f64();
+        assert_eq!((flat(0), flat(1), flat(8)), (0.0, 0.25, 2.0));
+    }
+
+    #[test]
+    fn send_window_ends_at_the_last_completed_send() {
+        let start = std::time::Instant::now();
+        let sent = |outcome: Outcome, millis: Option<u64>| TxMetrics {
+            outcome,
+            sent_at: millis.map(|millis| start + Duration::from_millis(millis)),
+            ..TxMetrics::new(None)
+        };
+        let metrics = vec![
+            sent(Outcome::Landed, Some(100)),
+            sent(Outcome::TimedOut, Some(2_500)),
+            sent(Outcome::Rejected, None),
+            sent(Outcome::Landed, Some(900)),
+        ];
+        assert_eq!(send_window(start, &metrics), Duration::from_millis(2_500));
+        assert_eq!(send_window(start, &metrics[2..3]), Duration::ZERO);
+    }
+}
@@ -1,60 +1,225 @@
+mod cli;
+mod confirmation;
+mod error;
+mod fees;
+mod load;
+mod message;
+mod nonce;
+mod progress;
+mod report;
+mod resend;
+mod simulate;
+mod stats;
+mod submit;
+mod transfer;
+mod wallets;
+
+use blockhash_cache::{BlockhashCache, Refresh};
+use clap::Parser;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    commitment_config::CommitmentConfig,
     pubkey::Pubkey,
-    signature::{read_keypair_file, Signer},
-    system_instruction,
-    transaction::Transaction,
+    signature::{read_keypair_file, Keypair, Signer},
 };
 use config::{Config, File};
 use serde::Deserialize;
-use std::{sync::Arc, time::{Duration, Instant}};
+use std::{path::PathBuf, sync::Arc, time::{Duration, Instant, SystemTime}};
 use tokio::{sync::Semaphore, task};
 use futures::stream::{FuturesUnordered, StreamExt};
 
+use cli::{BlockhashSource, Cli, Command};
+use confirmation::{Outcome, StageTimes, Tracker, TrackerKind};
+use error::TxError;
+use fees::ComputeBudgetConfig;
+use progress::Progress;
+use simulate::Simulation;
+use load::{LoadPlan, LoadSummary};
+use message::{MessageBuilder, TransactionConfig};
+use resend::{BlockHeights, ResendConfig};
+use transfer::{Sender, Transfer};
+
 #[derive(Debug, Deserialize)]
 struct AppConfig {
+    #[serde(default)]
     from_wallets: Vec<String>,
+    #[serde(default)]
     to_wallets: Vec<String>,
+    /// Каталог кошельков из `task2 setup`: каждый переводит следующему по кругу.
+    wallets_dir: Option<PathBuf>,
     amount: u64,
+    /// Способ отслеживания подтверждений: poll, batch или websocket.
+    #[serde(default)]
+    tracker: TrackerKind,
+    /// PubSub-адрес для `tracker: websocket`; по умолчанию выводится из RPC URL.
+    ws_url: Option<String>,
+    /// Инструкции бюджета вычислений и приоритетная комиссия.
+    #[serde(default)]
+    compute_budget: ComputeBudgetConfig,
+    /// Повторная отправка и лимит ожидания подтверждения.
+    #[serde(default)]
+    resend: ResendConfig,
+    /// Версия сообщения, таблица адресов и число переводов в транзакции.
+    #[serde(default)]
+    transaction: TransactionConfig,
 }
 
 #[derive(Debug)]
 struct TxMetrics {
     signature: String,
+    /// Сборка и подпись транзакции; `None`, если до сборки не дошло.
+    build_time: Option<Duration>,
+    /// Время симуляции; `None`, если симуляция не включалась или до неё не дошло.
+    simulate_time: Option<Duration>,
     send_time: Duration,
-    finalization_time: Duration,
+    /// Когда закончилась отправка; `None`, если транзакция не дошла до сети.
+    sent_at: Option<Instant>,
+    /// Время от окончания отправки до processed, confirmed и finalized.
+    stages: StageTimes,
     total_time: Duration,
-    finalized_successfully: bool,
+    /// Достигла ли транзакция целевого уровня подтверждения.
+    landed: bool,
+    outcome: Outcome,
+    /// Почему транзакция не дошла до целевого уровня.
+    error: Option<TxError>,
+    /// Сколько раз транзакция отправлялась повторно.
+    resends: u32,
+    /// Цена вычислительной единицы в микролампортах, если задавалась.
+    unit_price: Option<u64>,
+    /// Размер сериализованной транзакции.
+    tx_bytes: usize,
+    /// Возраст блокхэша из кэша на момент подписи.
+    blockhash_age: Option<Duration>,
+    /// Комиссия и потреблённые CU по симуляции.
+    fee: Option<u64>,
+    units_consumed: Option<u64>,
+}
+
+impl TxMetrics {
+    /// Метрики ещё не отправленной транзакции: итог `Rejected`, пока она не ушла в сеть.
+    fn new(unit_price: Option<u64>) -> Self {
+        Self {
+            signature: String::new(),
+            build_time: None,
+            simulate_time: None,
+            send_time: Duration::ZERO,
+            sent_at: None,
+            stages: Default::default(),
+            total_time: Duration::ZERO,
+            landed: false,
+            outcome: Outcome::Rejected,
+            error: None,
+            resends: 0,
+            unit_price,
+            tx_bytes: 0,
+            blockhash_age: None,
+            fee: None,
+            units_consumed: None,
+        }
+    }
+}
+
+/// Выводит время стадии или прочерк, если стадия не была достигнута.
+fn format_stage(stage: Option<Duration>) -> String {
+    stage.map(|d| format!("{:.2?}", d)).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_tx(tx_metrics: &TxMetrics) -> String {
+    format!(
+        "Tx Hash: {}\tSend Time: {:.2?}\tProcessed: {}\tConfirmed: {}\tFinalized Time: {}\tTotal Time: {:.2?}\tLanded: {}\tOutcome: {}\tResends: {}{}{}",
+        tx_metrics.signature,
+        tx_metrics.send_time,
+        format_stage(tx_metrics.stages.processed),
+        format_stage(tx_metrics.stages.confirmed),
+        format_stage(tx_metrics.stages.finalized),
+        tx_metrics.total_time,
+        if tx_metrics.landed { "Да" } else { "Нет" },
+        tx_metrics.outcome.name(),
+        tx_metrics.resends,
+        tx_metrics.simulate_time.map(|d| format!("\tSimulate Time: {:.2?}", d)).unwrap_or_default(),
+        tx_metrics.error.as_ref().map(|e| format!("\tError ({}): {}", e.kind(), e)).unwrap_or_default()
+    )
 }
 
-/// Ожидание финализации транзакции с таймаутом.
-/// Если транзакция финализируется до истечения timeout_duration,
-/// функция возвращает (время ожидания, true). Иначе – (время ожидания, false).
-async fn wait_for_finalization(client: Arc<RpcClient>, signature: &str) -> (Duration, bool) {
-    let start = Instant::now();
-    let timeout_duration = Duration::from_secs(30);
-    let mut success = false;
-
-    loop {
-        if start.elapsed() > timeout_duration {
-            break;
-        }
-        let statuses = client.get_signature_statuses(&[signature.parse().unwrap()]).await;
-        if let Ok(statuses) = statuses {
-            if let Some(Some(s)) = statuses.value.get(0) {
-                success = s.status.is_ok();
-                break;
+/// Один прогон бенчмарка: открытый цикл по плану нагрузки или по одному переводу на пару
+/// кошельков с ограничением параллельности.
+async fn run_benchmark(
+    sender: Arc<Sender>,
+    transfers: Arc<Vec<Transfer>>,
+    amount: u64,
+    load_plan: Option<LoadPlan>,
+    concurrency: usize,
+) -> (Vec<TxMetrics>, Option<LoadSummary>) {
+    let progress = Arc::clone(&sender.progress);
+    let report = |tx_metrics: &TxMetrics| {
+        progress.finished(tx_metrics);
+        progress.println(format_tx(tx_metrics));
+    };
+    if let Some(plan) = load_plan {
+        let (metrics, summary) = load::run(sender, transfers, amount, plan, report).await;
+        progress.finish();
+        return (metrics, Some(summary));
+    }
+
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut tasks = FuturesUnordered::new();
+    for index in 0..transfers.len() {
+        let (sender, transfers) = (Arc::clone(&sender), Arc::clone(&transfers));
+        let sem = Arc::clone(&semaphore);
+        tasks.push(task::spawn(async move {
+            let _permit = sem.acquire_owned().await;
+            sender.transfer(&transfers[index], amount).await
+        }));
+    }
+
+    let mut metrics = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok(tx_metrics) => {
+                report(&tx_metrics);
+                metrics.push(tx_metrics);
+            },
+            Err(e) => {
+                eprintln!("Ошибка задачи: {:?}", e);
             }
         }
-        tokio::time::sleep(Duration::from_millis(100)).await;
     }
-    (start.elapsed(), success)
+    progress.finish();
+    (metrics, None)
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Some(Command::Compare { baseline, candidate }) => {
+            let (a, b) = (report::Report::load(baseline)?, report::Report::load(candidate)?);
+            report::print_comparison(baseline, &a, candidate, &b);
+            return Ok(());
+        }
+        Some(Command::Setup { dir, count, lamports, funder, airdrop, rpc_url }) => {
+            let client = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+            let funding = match (funder, airdrop) {
+                (Some(path), false) => wallets::Funding::Master(Box::new(
+                    read_keypair_file(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+                )),
+                (None, true) => wallets::Funding::Airdrop,
+                (Some(_), true) | (None, false) => unreachable!("clap требует ровно один из --funder и --airdrop"),
+            };
+            wallets::setup(&client, dir, *count, *lamports, funding).await?;
+            return Ok(());
+        }
+        Some(Command::Teardown { dir, to, rpc_url }) => {
+            let client = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+            wallets::teardown(&client, dir, to).await?;
+            return Ok(());
+        }
+        None => {}
+    }
+
     let settings = Config::builder()
-        .add_source(File::with_name("/home/ando/documents/other/test/task2/config.yaml"))
+        .add_source(File::from(cli.config.as_path()))
         .build()?;
     let config: AppConfig = settings.try_deserialize()?;
 
@@ -63,124 +228,199 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
-    let rpc_url = "http://127.0.0.1:8899";
-    let semaphore = Arc::new(Semaphore::new(5));
-    let client = Arc::new(RpcClient::new(rpc_url.to_string()));
-
-    let mut tasks = FuturesUnordered::new();
+    let load_plan = cli.load_plan()?;
+    config.compute_budget.validate()?;
+    let simulation = cli.simulation();
+    if simulation == Simulation::DryRun {
+        // В холостом прогоне не тратятся лампорты, в том числе на подготовку.
+        if cli.blockhash == BlockhashSource::Nonce {
+            return Err("--dry-run не создаёт nonce-аккаунты, используйте --blockhash recent или cached".into());
+        }
+        if config.transaction.lookup_table.as_deref() == Some("create") {
+            return Err("--dry-run не создаёт таблицу адресов, укажите в lookup_table адрес готовой".into());
+        }
+    }
 
+    let mut transfers = Vec::with_capacity(config.from_wallets.len());
+    // Отправители с нечитаемым файлом ключей не останавливают прогон: в каждом прогоне
+    // они попадают в результаты отклонёнными с ошибкой `keypair`.
+    let mut unreadable = Vec::new();
+    // Отправители из `from_wallets`: `task2 teardown` их не знает, поэтому их nonce-аккаунты
+    // закрываются в конце прогона.
+    let mut config_senders = Vec::new();
     for (from_path, to_addr) in config.from_wallets.iter().zip(config.to_wallets.iter()) {
-        let keypair = read_keypair_file(&from_path)
-            .unwrap_or_else(|_| panic!("Не удалось прочитать файл ключей: {}", from_path));
-        let from_pubkey = keypair.pubkey();
         let to_pubkey = to_addr.parse::<Pubkey>()
-            .expect("Неверный формат публичного ключа получателя");
-        let instruction = system_instruction::transfer(&from_pubkey, &to_pubkey, config.amount);
-
-        let client = Arc::clone(&client);
-        let sem = Arc::clone(&semaphore);
-
-        tasks.push(task::spawn(async move {
-            let _permit = sem.acquire_owned().await;
-            let recent_blockhash = client.get_latest_blockhash().await
-                .expect("Не удалось получить последний блокхэш");
-
-            let tx = Transaction::new_signed_with_payer(
-                &[instruction],
-                Some(&from_pubkey),
-                &[&keypair],
-                recent_blockhash,
-            );
-
-            let start_send = Instant::now();
-            let signature = client.send_transaction(&tx).await
-                .expect("Ошибка отправки транзакции");
-            let send_time = start_send.elapsed();
-
-            let (finalization_time, finalized_successfully) =
-                wait_for_finalization(Arc::clone(&client), &signature.to_string()).await;
-            let total_time = send_time + finalization_time;
-
-            TxMetrics {
-                signature: signature.to_string(),
-                send_time,
-                finalization_time,
-                total_time,
-                finalized_successfully,
+            .map_err(|e| format!("Неверный формат публичного ключа получателя {}: {}", to_addr, e))?;
+        match read_keypair_file(from_path) {
+            Ok(keypair) => {
+                let from = Arc::new(keypair);
+                config_senders.push(Arc::clone(&from));
+                transfers.push(Transfer { from, to: to_pubkey });
             }
-        }));
+            Err(e) => unreadable.push(TxError::Keypair(format!("Не удалось прочитать файл ключей {}: {}", from_path, e))),
+        }
+    }
+    if let Some(dir) = &config.wallets_dir {
+        let keypairs: Vec<Arc<Keypair>> = wallets::load(dir)?.into_iter().map(Arc::new).collect();
+        if keypairs.len() < 2 {
+            return Err(format!("в {} меньше двух кошельков, создайте их через `task2 setup`", dir.display()).into());
+        }
+        for (index, from) in keypairs.iter().enumerate() {
+            let to = keypairs[(index + 1) % keypairs.len()].pubkey();
+            transfers.push(Transfer { from: Arc::clone(from), to });
+        }
     }
+    if transfers.is_empty() {
+        if !unreadable.is_empty() {
+            return Err(format!("не прочитан ни один файл ключей отправителей, первая ошибка: {}", unreadable[0]).into());
+        }
+        return Err("в конфиге нет ни одной пары кошельков".into());
+    }
+    let transfers = Arc::new(transfers);
+
+    let commitment: CommitmentConfig = cli.commitment.into();
+    let client = Arc::new(RpcClient::new_with_commitment(cli.rpc_url.clone(), commitment));
+    let started_at = SystemTime::now();
+    let ws_url = config.ws_url.clone().unwrap_or_else(|| rpc_urls::websocket_url(&cli.rpc_url));
+    let tracker = Arc::new(Tracker::new(config.tracker, Arc::clone(&client), &ws_url).await?);
+    let block_heights = BlockHeights::spawn(Arc::clone(&client)).await?;
+    let destinations = transfers.iter().map(|t| t.to).collect();
+    let message = Arc::new(MessageBuilder::new(&config.transaction, &client, destinations, &transfers[0].from).await?);
+    let nonces = match cli.blockhash {
+        BlockhashSource::Nonce => {
+            let senders: Vec<Arc<Keypair>> = transfers.iter().map(|t| Arc::clone(&t.from)).collect();
+            Some(Arc::new(nonce::NonceAccounts::prepare(&client, &senders).await?))
+        }
+        BlockhashSource::Recent | BlockhashSource::Cached => None,
+    };
+    let blockhashes = match cli.blockhash {
+        BlockhashSource::Cached => Some(
+            BlockhashCache::spawn(Arc::clone(&client), Refresh::Interval(cli.blockhash_refresh))
+                .await
+                .map_err(|e| format!("Не удалось получить блокхэш: {}", e))?,
+        ),
+        BlockhashSource::Recent | BlockhashSource::Nonce => None,
+    };
+    let submitter = Arc::new(submit::Submitter::new(cli.send_mode, Arc::clone(&client), &ws_url).await?);
 
+    let recent_fee = match config.compute_budget.recent_fee_percentile {
+        Some(pct) => {
+            let accounts = transfers.iter().flat_map(|t| [t.from.pubkey(), t.to]);
+            let fee = fees::recent_prioritization_fee(&client, accounts, pct).await?;
+            println!("Цена CU по getRecentPrioritizationFees (p{}): {} микролампортов", pct, fee);
+            Some(fee)
+        }
+        None => None,
+    };
+    let budgets = config.compute_budget.budgets(recent_fee);
+    let sweep = !config.compute_budget.sweep.is_empty();
+
+    let run_start = Instant::now();
     let mut metrics = Vec::new();
+    let mut load_summary = None;
+    let mut sweep_stats = Vec::new();
+    let mut sweep_levels = Vec::new();
+    for budget in &budgets {
+        if sweep {
+            println!("\nУровень приоритетной комиссии: {} микролампортов/CU", budget.unit_price.unwrap_or(0));
+        }
+        println!("Результаты транзакций:");
+        let sender = Arc::new(Sender {
+            client: Arc::clone(&client),
+            submitter: Arc::clone(&submitter),
+            message: Arc::clone(&message),
+            tracker: Arc::clone(&tracker),
+            commitment,
+            budget: *budget,
+            resend: config.resend,
+            block_heights: block_heights.clone(),
+            nonces: nonces.clone(),
+            blockhashes: blockhashes.clone(),
+            simulation,
+            progress: Progress::start(match &load_plan {
+                Some(plan) => plan.count,
+                None => Some(transfers.len()),
+            }),
+        });
+        let level_start = Instant::now();
+        let (mut level_metrics, level_load) =
+            run_benchmark(sender, Arc::clone(&transfers), config.amount, load_plan, cli.concurrency).await;
+        for error in &unreadable {
+            let tx_metrics = TxMetrics { error: Some(error.clone()), ..TxMetrics::new(budget.unit_price) };
+            println!("{}", format_tx(&tx_metrics));
+            level_metrics.push(tx_metrics);
+        }
 
-    println!("Результаты транзакций:");
-    while let Some(result) = tasks.next().await {
-        match result {
-            Ok(tx_metrics) => {
-                println!(
-                    "Tx Hash: {}\tSend Time: {:.2?}\tFinalization Time: {:.2?}\tTotal Time: {:.2?}\tFinalized: {}",
-                    tx_metrics.signature,
-                    tx_metrics.send_time,
-                    tx_metrics.finalization_time,
-                    tx_metrics.total_time,
-                    if tx_metrics.finalized_successfully { "Да" } else { "Нет" }
-                );
-                metrics.push(tx_metrics);
-            },
-            Err(e) => {
-                eprintln!("Ошибка задачи: {:?}", e);
+        if sweep {
+            let unit_price = budget.unit_price.unwrap_or(0);
+            let level_stats = stats::RunStats::from_metrics(&level_metrics, level_start.elapsed(), simulation);
+            if let Some(summary) = &level_load {
+                summary.print();
             }
+            sweep_levels.push(report::SweepLevel::new(unit_price, &level_stats, level_load));
+            sweep_stats.push((unit_price, level_stats));
+        } else {
+            load_summary = level_load;
         }
+        metrics.extend(level_metrics);
     }
 
-    let total_txs = metrics.len();
-    let successful_txs = metrics.iter().filter(|m| m.finalized_successfully).count();
-    let success_percentage = if total_txs > 0 {
-        (successful_txs as f64 / total_txs as f64) * 100.0
-    } else {
-        0.0
-    };
+    let run_elapsed = run_start.elapsed();
 
+    let run_stats = stats::RunStats::from_metrics(&metrics, run_elapsed, simulation);
     if !metrics.is_empty() {
-        let (mut total_send, mut total_final, mut total_total) = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
-        let (mut min_send, mut max_send) = (metrics[0].send_time, metrics[0].send_time);
-        let (mut min_final, mut max_final) = (metrics[0].finalization_time, metrics[0].finalization_time);
-        let (mut min_total, mut max_total) = (metrics[0].total_time, metrics[0].total_time);
-
-        for m in &metrics {
-            total_send += m.send_time;
-            total_final += m.finalization_time;
-            total_total += m.total_time;
-
-            if m.send_time < min_send { min_send = m.send_time; }
-            if m.send_time > max_send { max_send = m.send_time; }
-            if m.finalization_time < min_final { min_final = m.finalization_time; }
-            if m.finalization_time > max_final { max_final = m.finalization_time; }
-            if m.total_time < min_total { min_total = m.total_time; }
-            if m.total_time > max_total { max_total = m.total_time; }
-        }
-
-        let count = metrics.len() as u32;
-        let avg_send = total_send / count;
-        let avg_final = total_final / count;
-        let avg_total = total_total / count;
-
-        println!("\nСтатистика времени отправки транзакций:");
-        println!("Минимальное время: {:.2?}", min_send);
-        println!("Максимальное время: {:.2?}", max_send);
-        println!("Среднее время: {:.2?}", avg_send);
-
-        println!("\nСтатистика времени финализации транзакций:");
-        println!("Минимальное время: {:.2?}", min_final);
-        println!("Максимальное время: {:.2?}", max_final);
-        println!("Среднее время: {:.2?}", avg_final);
-
-        println!("\nОбщая статистика времени транзакций:");
-        println!("Минимальное время: {:.2?}", min_total);
-        println!("Максимальное время: {:.2?}", max_total);
-        println!("Среднее время: {:.2?}", avg_total);
-
-        println!("\nПроцент успешно финализированных транзакций: {:.2}%", success_percentage);
+        run_stats.print();
+    }
+    if let Some(summary) = &load_summary {
+        summary.print();
+    }
+    if sweep {
+        fees::print_sweep(&sweep_stats);
+    }
+
+    if cli.json.is_some() || cli.csv.is_some() {
+        let (cluster_version, feature_set) = match client.get_version().await {
+            Ok(version) => (Some(version.solana_core), version.feature_set),
+            Err(e) => {
+                eprintln!("Не удалось получить версию кластера: {}", e);
+                (None, None)
+            }
+        };
+        let metadata = report::RunMetadata {
+            rpc_url: cli.rpc_url.clone(),
+            commitment: commitment.commitment.to_string(),
+            concurrency: cli.concurrency,
+            tracker: config.tracker.name().to_string(),
+            send_mode: cli.send_mode.name().to_string(),
+            blockhash: cli.blockhash.name().to_string(),
+            simulation: simulation.name().to_string(),
+            tx_version: config.transaction.version.name().to_string(),
+            transfers_per_tx: config.transaction.transfers_per_tx,
+            lookup_table: message.lookup_table().map(|table| table.to_string()),
+            amount: config.amount,
+            started_at: humantime::format_rfc3339_seconds(started_at).to_string(),
+            cluster_version,
+            feature_set,
+            unit_limit: config.compute_budget.unit_limit,
+            unit_price: if sweep { None } else { budgets[0].unit_price },
+        };
+        let mut report = report::Report::new(metadata, &metrics, &run_stats, load_summary);
+        report.sweep = sweep_levels;
+        if let Some(path) = &cli.json {
+            report.write_json(path)?;
+            println!("\nJSON-отчёт сохранён в {}", path.display());
+        }
+        if let Some(path) = &cli.csv {
+            report.write_csv(path)?;
+            println!("\nCSV-отчёт сохранён в {}", path.display());
+        }
+    }
+
+    if nonces.is_some() && !config_senders.is_empty() {
+        let senders: Vec<&Keypair> = config_senders.iter().map(|k| k.as_ref()).collect();
+        if let Err(e) = nonce::withdraw_accounts(&client, &senders).await {
+            eprintln!("Не удалось закрыть nonce-аккаунты отправителей из конфига: {}", e);
+        }
     }
 
     Ok(())
@@ -0,0 +1,220 @@
+use std::{error::Error, time::Duration};
+
+use serde::Deserialize;
+use solana_address_lookup_table_interface::{
+    instruction::{create_lookup_table, extend_lookup_table},
+    state::AddressLookupTable,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, AddressLookupTableAccount, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+/// Сколько адресов добавляется в таблицу одной транзакцией.
+const EXTEND_PER_TX: usize = 20;
+
+/// Формат сообщения транзакций бенчмарка.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxVersion {
+    #[default]
+    Legacy,
+    V0,
+}
+
+impl TxVersion {
+    pub fn name(self) -> &'static str {
+        match self {
+            TxVersion::Legacy => "legacy",
+            TxVersion::V0 => "v0",
+        }
+    }
+}
+
+/// Секция `transaction` конфига.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionConfig {
+    #[serde(default)]
+    pub version: TxVersion,
+    /// Инструкций перевода в одной транзакции: первый идёт получателю из пары,
+    /// остальные — следующим по списку получателям.
+    #[serde(default = "default_transfers_per_tx")]
+    pub transfers_per_tx: usize,
+    /// Таблица адресов для v0: `create`, чтобы создать её и заполнить адресами
+    /// получателей, или адрес уже созданной таблицы.
+    pub lookup_table: Option<String>,
+}
+
+fn default_transfers_per_tx() -> usize {
+    1
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        Self { version: TxVersion::Legacy, transfers_per_tx: default_transfers_per_tx(), lookup_table: None }
+    }
+}
+
+/// Собирает транзакции перевода в формате из конфига.
+pub struct MessageBuilder {
+    version: TxVersion,
+    transfers_per_tx: usize,
+    /// Получатели без повторов, по ним идут дополнительные переводы транзакции.
+    destinations: Vec<Pubkey>,
+    lookup_table: Option<AddressLookupTableAccount>,
+}
+
+impl MessageBuilder {
+    /// Проверяет конфиг и при необходимости создаёт таблицу адресов; её владелец
+    /// и плательщик — `authority`.
+    pub async fn new(
+        config: &TransactionConfig,
+        client: &RpcClient,
+        destinations: Vec<Pubkey>,
+        authority: &Keypair,
+    ) -> Result<Self, Box<dyn Error>> {
+        if config.transfers_per_tx == 0 {
+            return Err("transaction: transfers_per_tx должен быть не меньше 1".into());
+        }
+        if config.lookup_table.is_some() && config.version != TxVersion::V0 {
+            return Err("transaction: lookup_table поддерживается только для version: v0".into());
+        }
+        let mut unique = Vec::with_capacity(destinations.len());
+        for destination in destinations {
+            if !unique.contains(&destination) {
+                unique.push(destination);
+            }
+        }
+
+        let lookup_table = match config.lookup_table.as_deref() {
+            None => None,
+            Some(setting) => {
+                let address = if setting == "create" {
+                    let address = create_table(client, authority, &unique).await?;
+                    println!("Создана таблица адресов {} на {} получателей", address, unique.len());
+                    address
+                } else {
+                    setting
+                        .parse()
+                        .map_err(|e| format!("transaction: неверный адрес lookup_table {}: {}", setting, e))?
+                };
+                Some(fetch_table(client, address).await?)
+            }
+        };
+        Ok(Self { version: config.version, transfers_per_tx: config.transfers_per_tx, destinations: unique, lookup_table })
+    }
+
+    /// Адрес таблицы для метаданных отчёта.
+    pub fn lookup_table(&self) -> Option<Pubkey> {
+        self.lookup_table.as_ref().map(|table| table.key)
+    }
+
+    /// Получатели переводов транзакции: `to` и следующие за ним по списку.
+    fn recipients(&self, to: Pubkey) -> impl Iterator<Item = Pubkey> + '_ {
+        let start = self.destinations.iter().position(|d| *d == to);
+        let rest = start.into_iter().flat_map(move |start| {
+            self.destinations.iter().cycle().skip(start + 1).take(self.transfers_per_tx - 1).copied()
+        });
+        std::iter::once(to).chain(rest)
+    }
+
+    /// Legacy-сообщения с одним переводом без таблицы адресов.
+    #[cfg(test)]
+    pub fn legacy(destinations: Vec<Pubkey>) -> Self {
+        Self { version: TxVersion::Legacy, transfers_per_tx: 1, destinations, lookup_table: None }
+    }
+
+    /// Подписанная транзакция: сначала `prefix` (бюджет вычислений), затем переводы.
+    pub fn build(
+        &self,
+        from: &Keypair,
+        to: Pubkey,
+        lamports: u64,
+        prefix: Vec<Instruction>,
+        blockhash: Hash,
+    ) -> Result<VersionedTransaction, String> {
+        let payer = from.pubkey();
+        let mut instructions = prefix;
+        instructions.extend(self.recipients(to).map(|to| system_instruction::transfer(&payer, &to, lamports)));
+        match self.version {
+            TxVersion::Legacy => {
+                Ok(Transaction::new_signed_with_payer(&instructions, Some(&payer), &[from], blockhash).into())
+            }
+            TxVersion::V0 => {
+                let tables = self.lookup_table.as_slice();
+                let message = v0::Message::try_compile(&payer, &instructions, tables, blockhash)
+                    .map_err(|e| e.to_string())?;
+                VersionedTransaction::try_new(VersionedMessage::V0(message), &[from]).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+async fn send(client: &RpcClient, instruction: Instruction, payer: &Keypair) -> Result<(), Box<dyn Error>> {
+    let blockhash = client.get_latest_blockhash().await?;
+    let message = Message::new_with_blockhash(&[instruction], Some(&payer.pubkey()), &blockhash);
+    client.send_and_confirm_transaction(&Transaction::new(&[payer], message, blockhash)).await?;
+    Ok(())
+}
+
+/// Создаёт таблицу адресов и дожидается слота, с которого добавленные адреса доступны.
+async fn create_table(client: &RpcClient, authority: &Keypair, addresses: &[Pubkey]) -> Result<Pubkey, Box<dyn Error>> {
+    let recent_slot = client.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+    let (create, address) = create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+    send(client, create, authority).await?;
+    for chunk in addresses.chunks(EXTEND_PER_TX) {
+        let extend = extend_lookup_table(address, authority.pubkey(), Some(authority.pubkey()), chunk.to_vec());
+        send(client, extend, authority).await?;
+    }
+    // Адреса, добавленные в слоте N, можно использовать с слота N + 1.
+    let extended_at = client.get_slot().await?;
+    while client.get_slot().await? <= extended_at {
+        tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+    Ok(address)
+}
+
+async fn fetch_table(client: &RpcClient, address: Pubkey) -> Result<AddressLookupTableAccount, Box<dyn Error>> {
+    let account = client
+        .get_account(&address)
+        .await
+        .map_err(|e| format!("таблица адресов {}: {}", address, e))?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| format!("таблица адресов {}: {}", address, e))?;
+    Ok(AddressLookupTableAccount { key: address, addresses: table.addresses.to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v0_message_resolves_recipients_through_lookup_table() {
+        let destinations: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: destinations.clone() };
+        let builder = MessageBuilder {
+            version: TxVersion::V0,
+            transfers_per_tx: 3,
+            destinations: destinations.clone(),
+            lookup_table: Some(table),
+        };
+        let from = Keypair::new();
+
+        let recipients: Vec<Pubkey> = builder.recipients(destinations[2]).collect();
+        assert_eq!(recipients, vec![destinations[2], destinations[3], destinations[0]]);
+
+        let tx = builder.build(&from, destinations[2], 1_000, Vec::new(), Hash::default()).unwrap();
+        let VersionedMessage::V0(message) = &tx.message else { panic!("ожидалось v0-сообщение") };
+        // В статических ключах только плательщик и System Program, получатели — из таблицы.
+        assert_eq!(message.account_keys.len(), 2);
+        assert_eq!(message.address_table_lookups[0].writable_indexes, vec![0, 2, 3]);
+        assert_eq!(message.instructions.len(), 3);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, error::Error, sync::Arc};
+
+use solana_client::{nonblocking::rpc_client::RpcClient, nonce_utils::nonblocking as nonce_utils};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    nonce::{state::Data, State},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::message::MessageBuilder;
+
+/// Сид адреса nonce-аккаунта: адрес выводится из ключа отправителя, поэтому файлы не нужны.
+const NONCE_SEED: &str = "task2-nonce";
+
+/// Адрес nonce-аккаунта отправителя.
+pub(crate) fn nonce_address(authority: &Pubkey) -> Pubkey {
+    Pubkey::create_with_seed(authority, NONCE_SEED, &system_program::id())
+        .expect("сид короче MAX_SEED_LEN")
+}
+
+struct NonceAccount {
+    address: Pubkey,
+    /// Каждая транзакция сдвигает nonce, поэтому у отправителя в полёте не больше одной.
+    in_use: Mutex<()>,
+}
+
+/// Nonce-аккаунты отправителей бенчмарка, по одному на ключ.
+pub struct NonceAccounts {
+    accounts: HashMap<Pubkey, NonceAccount>,
+}
+
+/// Право на одну транзакцию с nonce отправителя: держится, пока транзакция не подтвердится.
+pub struct NonceLease<'a> {
+    _in_use: MutexGuard<'a, ()>,
+    /// Текущее значение nonce, используется вместо недавнего блокхэша.
+    blockhash: Hash,
+    /// Первая инструкция транзакции.
+    advance: Instruction,
+}
+
+impl<'a> NonceLease<'a> {
+    fn new(in_use: MutexGuard<'a, ()>, address: &Pubkey, authority: &Pubkey, data: &Data) -> Self {
+        Self {
+            _in_use: in_use,
+            blockhash: data.blockhash(),
+            advance: system_instruction::advance_nonce_account(address, authority),
+        }
+    }
+
+    /// Собирает транзакцию с nonce вместо блокхэша: `advance` идёт первой инструкцией,
+    /// иначе узел не примет nonce.
+    pub fn build(
+        &self,
+        message: &MessageBuilder,
+        from: &Keypair,
+        to: Pubkey,
+        lamports: u64,
+        instructions: Vec<Instruction>,
+    ) -> Result<VersionedTransaction, String> {
+        let prefix = std::iter::once(self.advance.clone()).chain(instructions).collect();
+        message.build(from, to, lamports, prefix, self.blockhash)
+    }
+}
+
+impl NonceAccounts {
+    /// Находит nonce-аккаунты отправителей и создаёт недостающие; их владелец и плательщик — сам отправитель.
+    pub async fn prepare(client: &RpcClient, senders: &[Arc<Keypair>]) -> Result<Self, Box<dyn Error>> {
+        let mut unique: Vec<&Arc<Keypair>> = Vec::new();
+        for sender in senders {
+            if !unique.iter().any(|s| s.pubkey() == sender.pubkey()) {
+                unique.push(sender);
+            }
+        }
+
+        let rent = client.get_minimum_balance_for_rent_exemption(State::size()).await?;
+        let mut accounts = HashMap::new();
+        for sender in unique {
+            let authority = sender.pubkey();
+            let address = nonce_address(&authority);
+            if client.get_account_with_commitment(&address, client.commitment()).await?.value.is_none() {
+                let instructions = system_instruction::create_nonce_account_with_seed(
+                    &authority, &address, &authority, NONCE_SEED, &authority, rent,
+                );
+                let blockhash = client.get_latest_blockhash().await?;
+                let tx = Transaction::new_signed_with_payer(&instructions, Some(&authority), &[sender], blockhash);
+                client
+                    .send_and_confirm_transaction(&tx)
+                    .await
+                    .map_err(|e| format!("не удалось создать nonce-аккаунт {} для {}: {}", address, authority, e))?;
+                println!("Создан nonce-аккаунт {} для {}", address, authority);
+            }
+            accounts.insert(authority, NonceAccount { address, in_use: Mutex::new(()) });
+        }
+        Ok(Self { accounts })
+    }
+
+    /// Ждёт, пока nonce отправителя освободится, и читает его текущее значение.
+    pub async fn acquire(&self, client: &RpcClient, authority: &Pubkey) -> Result<NonceLease<'_>, String> {
+        let account = self
+            .accounts
+            .get(authority)
+            .ok_or_else(|| format!("нет nonce-аккаунта для {}", authority))?;
+        let in_use = account.in_use.lock().await;
+        let data = nonce_utils::get_account_with_commitment(client, &account.address, client.commitment())
+            .await
+            .and_then(|account| nonce_utils::data_from_account(&account))
+            .map_err(|e| format!("nonce-аккаунт {}: {}", account.address, e))?;
+        Ok(NonceLease::new(in_use, &account.address, authority, &data))
+    }
+}
+
+/// Закрывает nonce-аккаунты кошельков: весь их баланс возвращается самому кошельку,
+/// комиссию платит он же. Кошельки без nonce-аккаунта пропускаются.
+pub async fn withdraw_accounts(client: &RpcClient, wallets: &[&Keypair]) -> Result<(), Box<dyn Error>> {
+    for wallet in wallets {
+        let authority = wallet.pubkey();
+        let address = nonce_address(&authority);
+        let Some(account) = client.get_account_with_commitment(&address, client.commitment()).await?.value else {
+            continue;
+        };
+        let instruction = system_instruction::withdraw_nonce_account(&address, &authority, &authority, account.lamports);
+        let blockhash = client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(&[instruction], Some(&authority), &[wallet], blockhash);
+        match client.send_and_confirm_transaction(&tx).await {
+            Ok(signature) => println!(
+                "Закрыт nonce-аккаунт {}: {} лампортов возвращено на {} ({})",
+                address, account.lamports, authority, signature
+            ),
+            Err(e) => eprintln!("Не удалось закрыть nonce-аккаунт {} для {}: {}", address, authority, e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction, nonce::state::DurableNonce, system_instruction::SystemInstruction,
+    };
+
+    use super::*;
+
+    #[test]
+    fn nonce_transaction_starts_with_advance_and_uses_stored_nonce() {
+        let from = Keypair::new();
+        let authority = from.pubkey();
+        let address = nonce_address(&authority);
+        let data = Data::new(authority, DurableNonce::from_blockhash(&Hash::new_unique()), 5_000);
+        let in_use = Mutex::new(());
+        let lease = NonceLease::new(in_use.try_lock().unwrap(), &address, &authority, &data);
+
+        let to = Pubkey::new_unique();
+        let unit_price = ComputeBudgetInstruction::set_compute_unit_price(1_000);
+        let tx = lease.build(&MessageBuilder::legacy(vec![to]), &from, to, 1_000, vec![unit_price]).unwrap();
+
+        assert_eq!(*tx.message.recent_blockhash(), data.blockhash());
+        let keys = tx.message.static_account_keys();
+        let instructions = tx.message.instructions();
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(keys[instructions[0].program_id_index as usize], system_program::id());
+        assert_eq!(keys[instructions[0].accounts[0] as usize], address);
+        assert_eq!(
+            bincode::deserialize::<SystemInstruction>(&instructions[0].data).unwrap(),
+            SystemInstruction::AdvanceNonceAccount
+        );
+        assert_eq!(keys[instructions[1].program_id_index as usize], solana_sdk::compute_budget::id());
+    }
+}
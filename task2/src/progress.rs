@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    io::IsTerminal,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::{confirmation::Outcome, stats::percentile, TxMetrics};
+
+/// Как часто обновляется строка состояния.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// За какой последний период считаются скользящие квантили задержки.
+const LATENCY_WINDOW: Duration = Duration::from_secs(10);
+
+/// Ход прогона: счётчики транзакций и задержки за последние `LATENCY_WINDOW`. Раз в
+/// `REFRESH_INTERVAL` выводится строка состояния: в терминале — строкой прогресса под
+/// результатами транзакций, иначе — отдельной строкой текста.
+pub struct Progress {
+    started: Instant,
+    total: Option<usize>,
+    sent: AtomicUsize,
+    in_flight: AtomicUsize,
+    confirmed: AtomicUsize,
+    finalized: AtomicUsize,
+    failed: AtomicUsize,
+    completed: AtomicUsize,
+    /// Слоты плана нагрузки, отброшенные без отправки: они тоже продвигают прогресс.
+    dropped: AtomicUsize,
+    /// Время завершения и общее время транзакций, дошедших до целевого уровня.
+    latencies: Mutex<VecDeque<(Instant, Duration)>>,
+    bar: Option<ProgressBar>,
+    done: AtomicBool,
+}
+
+impl Progress {
+    /// Запускает вывод состояния; `total` — сколько транзакций ожидается, если известно.
+    pub fn start(total: Option<usize>) -> Arc<Self> {
+        let bar = std::io::stdout().is_terminal().then(|| {
+            let bar = ProgressBar::with_draw_target(total.map(|t| t as u64), ProgressDrawTarget::stdout());
+            let template = match total {
+                Some(_) => "[{elapsed_precise}] {bar:30} {pos}/{len} {msg}",
+                None => "[{elapsed_precise}] {spinner} {pos} {msg}",
+            };
+            bar.set_style(ProgressStyle::with_template(template).expect("шаблон прогресса"));
+            bar
+        });
+        let progress = Arc::new(Self::new(total, bar));
+
+        let ticker = Arc::clone(&progress);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(REFRESH_INTERVAL).await;
+                if ticker.done.load(Ordering::Relaxed) {
+                    return;
+                }
+                ticker.render();
+            }
+        });
+        progress
+    }
+
+    fn new(total: Option<usize>, bar: Option<ProgressBar>) -> Self {
+        Self {
+            started: Instant::now(),
+            total,
+            sent: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            confirmed: AtomicUsize::new(0),
+            finalized: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            completed: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+            latencies: Mutex::new(VecDeque::new()),
+            bar,
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Транзакция отправлена и ждёт подтверждения.
+    pub fn sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Транзакция завершилась с итогом из `metrics`.
+    pub fn finished(&self, metrics: &TxMetrics) {
+        if metrics.outcome.reached_network() {
+            self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        }
+        if metrics.stages.confirmed.is_some() {
+            self.confirmed.fetch_add(1, Ordering::Relaxed);
+        }
+        if metrics.stages.finalized.is_some() {
+            self.finalized.fetch_add(1, Ordering::Relaxed);
+        }
+        if metrics.error.is_some() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        if metrics.outcome == Outcome::Landed {
+            self.latencies.lock().unwrap().push_back((Instant::now(), metrics.total_time));
+        }
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    /// Слот плана нагрузки отброшен из-за лимита транзакций в полёте.
+    pub fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    /// Печатает строку над строкой прогресса, не ломая её.
+    pub fn println(&self, line: String) {
+        match &self.bar {
+            Some(bar) => bar.println(line),
+            None => println!("{}", line),
+        }
+    }
+
+    /// Останавливает вывод состояния и убирает строку прогресса.
+    pub fn finish(&self) {
+        self.done.store(true, Ordering::Relaxed);
+        match &self.bar {
+            Some(bar) => bar.finish_and_clear(),
+            None => self.render(),
+        }
+    }
+
+    fn render(&self) {
+        let status = self.status();
+        match &self.bar {
+            Some(bar) => bar.set_message(status),
+            None => {
+                let completed = self.completed.load(Ordering::Relaxed) + self.dropped.load(Ordering::Relaxed);
+                let total = self.total.map(|t| format!("/{}", t)).unwrap_or_default();
+                println!("[{:.0?}] завершено {}{}, {}", self.started.elapsed(), completed, total, status);
+            }
+        }
+    }
+
+    fn status(&self) -> String {
+        let window = {
+            let mut latencies = self.latencies.lock().unwrap();
+            let now = Instant::now();
+            while latencies.front().is_some_and(|(at, _)| now.duration_since(*at) > LATENCY_WINDOW) {
+                latencies.pop_front();
+            }
+            let mut window: Vec<Duration> = latencies.iter().map(|(_, latency)| *latency).collect();
+            window.sort_unstable();
+            window
+        };
+        let latency = if window.is_empty() {
+            "-".to_string()
+        } else {
+            ["p50", "p90", "p99"]
+                .iter()
+                .zip([50.0, 90.0, 99.0])
+                .map(|(name, q)| format!("{}={:.0?}", name, percentile(&window, q)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let dropped = match self.dropped.load(Ordering::Relaxed) {
+            0 => String::new(),
+            dropped => format!("  отброшено {}", dropped),
+        };
+        format!(
+            "отправлено {}  в полёте {}  confirmed {}  finalized {}  ошибок {}{}  задержка за {}s: {}",
+            self.sent.load(Ordering::Relaxed),
+            self.in_flight.load(Ordering::Relaxed),
+            self.confirmed.load(Ordering::Relaxed),
+            self.finalized.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed),
+            dropped,
+            LATENCY_WINDOW.as_secs(),
+            latency
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{confirmation::StageTimes, error::TxError};
+
+    fn finished(outcome: Outcome, stages: StageTimes, error: Option<TxError>) -> TxMetrics {
+        TxMetrics { outcome, stages, error, total_time: Duration::from_millis(800), ..TxMetrics::new(None) }
+    }
+
+    #[test]
+    fn counters_follow_sent_and_finished_transactions() {
+        let progress = Progress::new(Some(5), None);
+        progress.sent();
+        progress.sent();
+        let landed = StageTimes {
+            processed: Some(Duration::from_millis(400)),
+            confirmed: Some(Duration::from_millis(600)),
+            finalized: Some(Duration::from_millis(800)),
+        };
+        progress.finished(&finished(Outcome::Landed, landed, None));
+        // Отклонённые и просимулированные не уходили в сеть и не уменьшают число в полёте.
+        let send_error = Some(TxError::Send("нет связи".to_string()));
+        progress.finished(&finished(Outcome::Rejected, StageTimes::default(), send_error));
+        progress.finished(&finished(Outcome::Simulated, StageTimes::default(), None));
+        progress.dropped();
+
+        let count = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+        assert_eq!(count(&progress.sent), 2);
+        assert_eq!(count(&progress.in_flight), 1);
+        assert_eq!(count(&progress.confirmed), 1);
+        assert_eq!(count(&progress.finalized), 1);
+        assert_eq!(count(&progress.failed), 1);
+        assert_eq!(count(&progress.completed), 3);
+        assert_eq!(count(&progress.dropped), 1);
+        assert!(progress
+            .status()
+            .starts_with("отправлено 2  в полёте 1  confirmed 1  finalized 1  ошибок 1  отброшено 1"));
+    }
+
+    #[test]
+    fn status_drops_latencies_older_than_window() {
+        let progress = Progress::new(None, None);
+        let now = Instant::now();
+        let old = now.checked_sub(LATENCY_WINDOW + Duration::from_secs(1)).unwrap();
+        progress.latencies.lock().unwrap().extend([
+            (old, Duration::from_secs(5)),
+            (now, Duration::from_millis(100)),
+            (now, Duration::from_millis(300)),
+        ]);
+
+        assert!(progress.status().ends_with("задержка за 10s: p50=100ms p90=300ms p99=300ms"));
+        assert_eq!(progress.latencies.lock().unwrap().len(), 2);
+        assert!(Progress::new(None, None).status().ends_with("задержка за 10s: -"));
+    }
+}
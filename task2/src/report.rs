@@ -0,0 +1,654 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    load::LoadSummary,
+    confirmation::Outcome,
+    simulate::Estimates,
+    stats::{OutcomeCounts, PhaseStats, RunStats, PERCENTILES},
+    TxMetrics,
+};
+
+/// Параметры прогона, по которым отчёты сопоставляются между днями и кластерами.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub rpc_url: String,
+    pub commitment: String,
+    pub concurrency: usize,
+    /// Способ отслеживания подтверждений (poll, batch, websocket).
+    #[serde(default)]
+    pub tracker: String,
+    /// Путь отправки транзакций (rpc, tpu).
+    #[serde(default)]
+    pub send_mode: String,
+    /// Источник блокхэша транзакций (recent, cached, nonce).
+    #[serde(default)]
+    pub blockhash: String,
+    /// Симуляция перед отправкой (off, before_send, dry_run).
+    #[serde(default)]
+    pub simulation: String,
+    /// Формат сообщения (legacy, v0), число переводов в транзакции и таблица адресов.
+    #[serde(default)]
+    pub tx_version: String,
+    #[serde(default = "default_transfers_per_tx")]
+    pub transfers_per_tx: usize,
+    #[serde(default)]
+    pub lookup_table: Option<String>,
+    pub amount: u64,
+    /// Время старта в RFC 3339.
+    pub started_at: String,
+    /// Версия узла из `getVersion`; `None`, если RPC её не вернул.
+    pub cluster_version: Option<String>,
+    pub feature_set: Option<u32>,
+    /// Бюджет вычислений; в режиме перебора цена своя у каждого уровня.
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+}
+
+fn default_transfers_per_tx() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxRecord {
+    pub signature: String,
+    pub send_ms: f64,
+    /// Время от отправки до стадии; `None`, если стадия не достигнута.
+    pub processed_ms: Option<f64>,
+    pub confirmed_ms: Option<f64>,
+    pub finalized_ms: Option<f64>,
+    pub total_ms: f64,
+    /// Достигла ли транзакция целевого уровня подтверждения.
+    pub landed: bool,
+    /// Цена вычислительной единицы в микролампортах.
+    #[serde(default)]
+    pub unit_price: Option<u64>,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Тип ошибки, `TxError::kind`.
+    #[serde(default)]
+    pub error_kind: Option<String>,
+    #[serde(default)]
+    pub resends: u32,
+    /// Размер сериализованной транзакции в байтах.
+    #[serde(default)]
+    pub tx_bytes: usize,
+    /// Возраст блокхэша из кэша на момент подписи.
+    #[serde(default)]
+    pub blockhash_age_ms: Option<f64>,
+    /// Комиссия и потреблённые CU по симуляции.
+    #[serde(default)]
+    pub fee_lamports: Option<u64>,
+    #[serde(default)]
+    pub units_consumed: Option<u64>,
+    /// Сборка и симуляция транзакции; `None`, если до них не дошло.
+    #[serde(default)]
+    pub build_ms: Option<f64>,
+    #[serde(default)]
+    pub simulate_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseSummary {
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub std_dev_ms: f64,
+    pub percentiles_ms: BTreeMap<String, f64>,
+}
+
+impl PhaseSummary {
+    fn new(stats: &PhaseStats) -> Self {
+        Self {
+            min_ms: ms(stats.min),
+            max_ms: ms(stats.max),
+            mean_ms: ms(stats.mean),
+            std_dev_ms: ms(stats.std_dev),
+            percentiles_ms: stats
+                .percentiles
+                .iter()
+                .map(|(name, value)| (name.to_string(), ms(*value)))
+                .collect(),
+        }
+    }
+
+    /// Значение статистики по имени из `stat_names`.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "min" => Some(self.min_ms),
+            "max" => Some(self.max_ms),
+            "mean" => Some(self.mean_ms),
+            "std_dev" => Some(self.std_dev_ms),
+            percentile => self.percentiles_ms.get(percentile).copied(),
+        }
+    }
+}
+
+/// Имена статистик фазы в порядке вывода.
+fn stat_names() -> impl Iterator<Item = &'static str> {
+    ["min", "mean", "std_dev"]
+        .into_iter()
+        .chain(PERCENTILES.iter().map(|(name, _)| *name))
+        .chain(["max"])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub submitted: usize,
+    /// Транзакции, достигшие целевого уровня подтверждения.
+    pub landed: usize,
+    pub success_percentage: f64,
+    pub elapsed_ms: f64,
+    pub submitted_tps: f64,
+    pub landed_tps: f64,
+    /// Число транзакций по итогам отправки.
+    #[serde(default)]
+    pub outcomes: OutcomeCounts,
+    /// Число транзакций по типам ошибок, только встретившиеся типы.
+    #[serde(default)]
+    pub errors: BTreeMap<String, usize>,
+    pub phases: Phases,
+    /// Фазы, неприменимые в этом прогоне: в `--dry-run` нет отправки и подтверждения.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_applicable: Vec<String>,
+    /// Возраст блокхэша из кэша; `None`, если кэш не использовался.
+    #[serde(default)]
+    pub blockhash_age: Option<PhaseSummary>,
+    /// Комиссии и CU по симуляции.
+    #[serde(default)]
+    pub estimates: Option<Estimates>,
+}
+
+/// Статистика фаз; `None`, если в фазе нет ни одного замера.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Phases {
+    pub send: Option<PhaseSummary>,
+    pub processed: Option<PhaseSummary>,
+    pub confirmed: Option<PhaseSummary>,
+    pub finalized: Option<PhaseSummary>,
+    pub total: Option<PhaseSummary>,
+    #[serde(default)]
+    pub build: Option<PhaseSummary>,
+    #[serde(default)]
+    pub simulate: Option<PhaseSummary>,
+}
+
+/// Фазы, которых нет в прогоне без отправки.
+const SEND_PHASES: [&str; 5] = ["send", "processed", "confirmed", "finalized", "total"];
+
+impl Summary {
+    fn new(stats: &RunStats) -> Self {
+        Self {
+            submitted: stats.throughput.submitted,
+            landed: stats.throughput.landed,
+            success_percentage: stats.success_percentage(),
+            elapsed_ms: ms(stats.throughput.elapsed),
+            submitted_tps: stats.throughput.submitted_tps(),
+            landed_tps: stats.throughput.landed_tps(),
+            outcomes: stats.outcomes,
+            errors: stats.errors.iter().map(|(kind, count)| (kind.to_string(), *count)).collect(),
+            phases: Phases {
+                send: stats.send.as_ref().map(PhaseSummary::new),
+                processed: stats.processed.as_ref().map(PhaseSummary::new),
+                confirmed: stats.confirmed.as_ref().map(PhaseSummary::new),
+                finalized: stats.finalized.as_ref().map(PhaseSummary::new),
+                total: stats.total.as_ref().map(PhaseSummary::new),
+                build: stats.build.as_ref().map(PhaseSummary::new),
+                simulate: stats.simulate.as_ref().map(PhaseSummary::new),
+            },
+            not_applicable: if stats.dry_run {
+                SEND_PHASES.iter().map(|phase| phase.to_string()).collect()
+            } else {
+                Vec::new()
+            },
+            blockhash_age: stats.blockhash_age.as_ref().map(PhaseSummary::new),
+            estimates: stats.estimates,
+        }
+    }
+
+    /// Фазы в порядке колонок CSV: сборка и симуляция идут последними.
+    fn phases(&self) -> [(&'static str, &Option<PhaseSummary>); 7] {
+        let phases = &self.phases;
+        [
+            ("send", &phases.send),
+            ("processed", &phases.processed),
+            ("confirmed", &phases.confirmed),
+            ("finalized", &phases.finalized),
+            ("total", &phases.total),
+            ("build", &phases.build),
+            ("simulate", &phases.simulate),
+        ]
+    }
+
+    fn run_values(&self) -> Vec<(&'static str, f64)> {
+        let totals = [
+            ("submitted", self.submitted as f64),
+            ("landed", self.landed as f64),
+            ("success_percentage", self.success_percentage),
+            ("elapsed_ms", self.elapsed_ms),
+            ("submitted_tps", self.submitted_tps),
+            ("landed_tps", self.landed_tps),
+        ];
+        let outcomes = Outcome::ALL.map(|outcome| (outcome.name(), self.outcomes.get(outcome) as f64));
+        let estimates = self.estimates.iter().flat_map(|e| {
+            [("fee_total", e.fee_total as f64), ("fee_mean", e.fee_mean), ("units_mean", e.units_mean)]
+        });
+        totals.into_iter().chain(outcomes).chain(estimates).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub metadata: RunMetadata,
+    pub summary: Summary,
+    /// Итоги режима нагрузки; отсутствуют в обычном прогоне.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<LoadSummary>,
+    /// Итоги по уровням приоритетной комиссии; пусто без режима перебора.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sweep: Vec<SweepLevel>,
+    pub transactions: Vec<TxRecord>,
+}
+
+/// Один уровень режима перебора приоритетной комиссии.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepLevel {
+    pub unit_price: u64,
+    pub summary: Summary,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load: Option<LoadSummary>,
+}
+
+impl SweepLevel {
+    pub fn new(unit_price: u64, stats: &RunStats, load: Option<LoadSummary>) -> Self {
+        Self { unit_price, summary: Summary::new(stats), load }
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Report {
+    pub fn new(metadata: RunMetadata, metrics: &[TxMetrics], stats: &RunStats, load: Option<LoadSummary>) -> Self {
+        Self {
+            metadata,
+            load,
+            sweep: Vec::new(),
+            summary: Summary::new(stats),
+            transactions: metrics
+                .iter()
+                .map(|m| TxRecord {
+                    signature: m.signature.clone(),
+                    send_ms: ms(m.send_time),
+                    processed_ms: m.stages.processed.map(ms),
+                    confirmed_ms: m.stages.confirmed.map(ms),
+                    finalized_ms: m.stages.finalized.map(ms),
+                    total_ms: ms(m.total_time),
+                    landed: m.landed,
+                    unit_price: m.unit_price,
+                    outcome: m.outcome,
+                    error: m.error.as_ref().map(ToString::to_string),
+                    error_kind: m.error.as_ref().map(|e| e.kind().to_string()),
+                    resends: m.resends,
+                    tx_bytes: m.tx_bytes,
+                    blockhash_age_ms: m.blockhash_age.map(ms),
+                    fee_lamports: m.fee,
+                    units_consumed: m.units_consumed,
+                    build_ms: m.build_time.map(ms),
+                    simulate_ms: m.simulate_time.map(ms),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?)
+    }
+
+    pub fn write_json(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        out.flush()
+    }
+
+    /// Одна таблица на весь отчёт: каждая строка несёт метаданные прогона, а колонка
+    /// `record` различает транзакции (`tx`), статистику фаз (`stat`) и итоги прогона (`run`).
+    /// В режиме перебора строки `stat` и `run` повторяются для каждого уровня с его `unit_price`.
+    /// Неприменимые в прогоне фазы помечаются в строках `stat` значением `n/a`.
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(fs::File::create(path)?);
+        writeln!(
+            out,
+            "started_at,rpc_url,cluster_version,commitment,tracker,send_mode,blockhash,simulation,tx_version,transfers_per_tx,lookup_table,concurrency,amount,unit_limit,unit_price,record,key,landed,send_ms,processed_ms,confirmed_ms,finalized_ms,total_ms,value,outcome,resends,error_kind,error,blockhash_age_ms,tx_bytes,fee_lamports,units_consumed,build_ms,simulate_ms"
+        )?;
+        let meta = &self.metadata;
+        let prefix = |unit_price: Option<u64>| {
+            [
+                csv_field(&meta.started_at),
+                csv_field(&meta.rpc_url),
+                csv_field(meta.cluster_version.as_deref().unwrap_or("")),
+                csv_field(&meta.commitment),
+                csv_field(&meta.tracker),
+                csv_field(&meta.send_mode),
+                csv_field(&meta.blockhash),
+                csv_field(&meta.simulation),
+                csv_field(&meta.tx_version),
+                meta.transfers_per_tx.to_string(),
+                csv_field(meta.lookup_table.as_deref().unwrap_or("")),
+                meta.concurrency.to_string(),
+                meta.amount.to_string(),
+                meta.unit_limit.map(|v| v.to_string()).unwrap_or_default(),
+                unit_price.map(|v| v.to_string()).unwrap_or_default(),
+            ]
+            .join(",")
+        };
+        let opt = |value: Option<f64>| value.map(|v| format!("{:.3}", v)).unwrap_or_default();
+
+        for tx in &self.transactions {
+            writeln!(
+                out,
+                "{},tx,{},{},{:.3},{},{},{},{:.3},,{},{},{},{},{},{},{},{},{},{}",
+                prefix(tx.unit_price),
+                tx.signature,
+                tx.landed,
+                tx.send_ms,
+                opt(tx.processed_ms),
+                opt(tx.confirmed_ms),
+                opt(tx.finalized_ms),
+                tx.total_ms,
+                tx.outcome.name(),
+                tx.resends,
+                tx.error_kind.as_deref().unwrap_or(""),
+                csv_field(tx.error.as_deref().unwrap_or("")),
+                opt(tx.blockhash_age_ms),
+                tx.tx_bytes,
+                tx.fee_lamports.map(|v| v.to_string()).unwrap_or_default(),
+                tx.units_consumed.map(|v| v.to_string()).unwrap_or_default(),
+                opt(tx.build_ms),
+                opt(tx.simulate_ms)
+            )?;
+        }
+        let summaries = std::iter::once((meta.unit_price, &self.summary))
+            .chain(self.sweep.iter().map(|level| (Some(level.unit_price), &level.summary)));
+        for (unit_price, summary) in summaries {
+            let prefix = prefix(unit_price);
+            for name in stat_names() {
+                let values = summary.phases().map(|(phase_name, phase)| {
+                    if summary.not_applicable.iter().any(|p| p == phase_name) {
+                        "n/a".to_string()
+                    } else {
+                        opt(phase.as_ref().and_then(|p| p.get(name)))
+                    }
+                });
+                let (send, build) = values.split_at(SEND_PHASES.len());
+                writeln!(out, "{},stat,{},,{},,,,,,,,,,{}", prefix, name, send.join(","), build.join(","))?;
+            }
+            for (name, value) in summary.run_values() {
+                writeln!(out, "{},run,{},,,,,,,{:.3},,,,,,,,,,", prefix, name, value)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Одна метрика двух отчётов.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    pub metric: String,
+    pub baseline: f64,
+    pub candidate: f64,
+}
+
+impl Delta {
+    pub fn change(&self) -> f64 {
+        self.candidate - self.baseline
+    }
+
+    pub fn change_percentage(&self) -> Option<f64> {
+        (self.baseline != 0.0).then(|| self.change() / self.baseline * 100.0)
+    }
+}
+
+/// Метрики, присутствующие в обоих отчётах.
+pub fn compare(baseline: &Report, candidate: &Report) -> Vec<Delta> {
+    let mut deltas = Vec::new();
+    for ((phase, a), (_, b)) in baseline.summary.phases().into_iter().zip(candidate.summary.phases()) {
+        let (Some(a), Some(b)) = (a, b) else { continue };
+        for name in stat_names() {
+            if let (Some(x), Some(y)) = (a.get(name), b.get(name)) {
+                deltas.push(Delta { metric: format!("{} {} ms", phase, name), baseline: x, candidate: y });
+            }
+        }
+    }
+    for ((name, x), (_, y)) in baseline.summary.run_values().into_iter().zip(candidate.summary.run_values()) {
+        deltas.push(Delta { metric: name.to_string(), baseline: x, candidate: y });
+    }
+    if let (Some(a), Some(b)) = (&baseline.load, &candidate.load) {
+        let load_values = |load: &LoadSummary| {
+            [
+                ("load target_tps", load.target_tps),
+                ("load achieved_tps", load.achieved_tps),
+                ("load drop_percentage", load.drop_percentage()),
+                ("load timeout_percentage", load.timeout_percentage()),
+            ]
+        };
+        for ((name, x), (_, y)) in load_values(a).into_iter().zip(load_values(b)) {
+            deltas.push(Delta { metric: name.to_string(), baseline: x, candidate: y });
+        }
+    }
+    for a in &baseline.sweep {
+        let Some(b) = candidate.sweep.iter().find(|b| b.unit_price == a.unit_price) else { continue };
+        let level_values = |level: &SweepLevel| {
+            let total_p50 = level.summary.phases.total.as_ref().and_then(|p| p.get("p50"));
+            [("landing %", Some(level.summary.success_percentage)), ("total p50 ms", total_p50)]
+        };
+        for ((name, x), (_, y)) in level_values(a).into_iter().zip(level_values(b)) {
+            if let (Some(x), Some(y)) = (x, y) {
+                let metric = format!("fee {} {}", a.unit_price, name);
+                deltas.push(Delta { metric, baseline: x, candidate: y });
+            }
+        }
+    }
+    deltas
+}
+
+fn describe(label: &str, path: &Path, meta: &RunMetadata) {
+    println!(
+        "{}: {} (старт {}, {}, версия {}, commitment {}, трекер {}, отправка {}, блокхэш {}, симуляция {}, транзакции {} x{}, параллельность {}, сумма {}, цена CU {})",
+        label,
+        path.display(),
+        meta.started_at,
+        meta.rpc_url,
+        meta.cluster_version.as_deref().unwrap_or("неизвестна"),
+        meta.commitment,
+        meta.tracker,
+        meta.send_mode,
+        meta.blockhash,
+        meta.simulation,
+        meta.tx_version,
+        meta.transfers_per_tx,
+        meta.concurrency,
+        meta.amount,
+        meta.unit_price.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string())
+    );
+}
+
+pub fn print_comparison(baseline_path: &Path, baseline: &Report, candidate_path: &Path, candidate: &Report) {
+    println!("Сравнение отчётов:");
+    describe("A", baseline_path, &baseline.metadata);
+    describe("B", candidate_path, &candidate.metadata);
+    if baseline.metadata.blockhash != candidate.metadata.blockhash {
+        // Так сравниваются durable nonce и недавний блокхэш: задержка — по фазам,
+        // доля попадания — success_percentage и число expired.
+        println!(
+            "Источник блокхэша различается: A={}, B={}",
+            baseline.metadata.blockhash, candidate.metadata.blockhash
+        );
+    }
+    println!();
+    println!("{:<28} {:>14} {:>14} {:>14} {:>10}", "МЕТРИКА", "A", "B", "B - A", "%");
+    for delta in compare(baseline, candidate) {
+        let percentage = delta
+            .change_percentage()
+            .map(|p| format!("{:+.2}", p))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<28} {:>14.3} {:>14.3} {:>+14.3} {:>10}",
+            delta.metric,
+            delta.baseline,
+            delta.candidate,
+            delta.change(),
+            percentage
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{confirmation::StageTimes, error::TxError, simulate::Simulation};
+
+    fn report(send_ms: u64, landed: bool, blockhash: &str) -> Report {
+        let metrics: Vec<TxMetrics> = (0..4)
+            .map(|i| TxMetrics {
+                signature: format!("sig{}", i),
+                send_time: Duration::from_millis(send_ms),
+                sent_at: None,
+                stages: StageTimes {
+                    processed: Some(Duration::from_millis(400)),
+                    confirmed: Some(Duration::from_millis(600)),
+                    finalized: Some(Duration::from_millis(1_000)),
+                },
+                total_time: Duration::from_millis(send_ms + 1_000),
+                landed: landed || i == 0,
+                outcome: if landed || i == 0 { Outcome::Landed } else { Outcome::Failed },
+                error: None,
+                resends: 0,
+                unit_price: None,
+                tx_bytes: 0,
+                blockhash_age: None,
+                fee: None,
+                units_consumed: None,
+                build_time: Some(Duration::from_millis(2)),
+                simulate_time: None,
+            })
+            .collect();
+        let stats = RunStats::from_metrics(&metrics, Duration::from_secs(2), Simulation::Off);
+        let metadata = RunMetadata {
+            rpc_url: "http://127.0.0.1:8899".to_string(),
+            commitment: "finalized".to_string(),
+            concurrency: 5,
+            tracker: "poll".to_string(),
+            send_mode: "rpc".to_string(),
+            blockhash: blockhash.to_string(),
+            simulation: "off".to_string(),
+            tx_version: "legacy".to_string(),
+            transfers_per_tx: 1,
+            lookup_table: None,
+            amount: 1_000,
+            started_at: "2025-03-17T16:30:07Z".to_string(),
+            cluster_version: Some("2.2.3".to_string()),
+            feature_set: None,
+            unit_limit: None,
+            unit_price: None,
+        };
+        Report::new(metadata, &metrics, &stats, None)
+    }
+
+    #[test]
+    fn compare_reports_matching_metrics() {
+        let baseline = report(100, true, "recent");
+        let candidate: Report =
+            serde_json::from_str(&serde_json::to_string(&report(50, false, "recent")).unwrap()).unwrap();
+
+        let deltas = compare(&baseline, &candidate);
+        let send_p50 = deltas.iter().find(|d| d.metric == "send p50 ms").unwrap();
+        assert_eq!((send_p50.baseline, send_p50.candidate), (100.0, 50.0));
+        assert_eq!(send_p50.change_percentage(), Some(-50.0));
+
+        let landed_tps = deltas.iter().find(|d| d.metric == "landed_tps").unwrap();
+        assert_eq!((landed_tps.baseline, landed_tps.candidate), (2.0, 0.5));
+    }
+
+    #[test]
+    fn compare_nonce_run_with_recent_blockhash_run() {
+        let recent = report(100, false, "recent");
+        let nonce: Report = serde_json::from_str(&serde_json::to_string(&report(300, true, "nonce")).unwrap()).unwrap();
+        assert_eq!((recent.metadata.blockhash.as_str(), nonce.metadata.blockhash.as_str()), ("recent", "nonce"));
+
+        let deltas = compare(&recent, &nonce);
+        let metric = |name: &str| deltas.iter().find(|d| d.metric == name).unwrap();
+        assert_eq!((metric("total p50 ms").baseline, metric("total p50 ms").candidate), (1_100.0, 1_300.0));
+        assert_eq!(
+            (metric("success_percentage").baseline, metric("success_percentage").candidate),
+            (25.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn dry_run_summary_has_build_and_simulate_phases() {
+        let row = |outcome: Outcome, simulate_ms: Option<u64>, error: Option<TxError>| TxMetrics {
+            outcome,
+            error,
+            build_time: simulate_ms.map(|_| Duration::from_millis(2)),
+            simulate_time: simulate_ms.map(Duration::from_millis),
+            ..TxMetrics::new(None)
+        };
+        let metrics = vec![
+            row(Outcome::Simulated, Some(40), None),
+            row(Outcome::Simulated, Some(60), None),
+            row(Outcome::Rejected, Some(50), Some(TxError::Simulation("InsufficientFunds".to_string()))),
+            row(Outcome::Rejected, None, Some(TxError::Keypair("нет файла".to_string()))),
+        ];
+        let stats = RunStats::from_metrics(&metrics, Duration::from_secs(1), Simulation::DryRun);
+        let summary = serde_json::to_value(Summary::new(&stats)).unwrap();
+
+        assert_eq!(summary["phases"]["build"]["max_ms"], 2.0);
+        assert_eq!(summary["phases"]["simulate"]["percentiles_ms"]["p50"], 50.0);
+        assert!(summary["phases"]["send"].is_null() && summary["phases"]["total"].is_null());
+        assert_eq!(summary["not_applicable"], serde_json::json!(SEND_PHASES));
+        // Две из трёх проведённых симуляций прошли; до симуляции не дошла одна транзакция.
+        assert_eq!(summary["success_percentage"].as_f64().unwrap().round(), 67.0);
+
+        let off = RunStats::from_metrics(&metrics, Duration::from_secs(1), Simulation::Off);
+        assert!(serde_json::to_value(Summary::new(&off)).unwrap().get("not_applicable").is_none());
+    }
+
+    #[test]
+    fn csv_rows_follow_the_header() {
+        let path = std::env::temp_dir().join(format!("task2-report-{}.csv", std::process::id()));
+        report(100, false, "recent").write_csv(&path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut lines = csv.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert!(rows.iter().all(|row| row.len() == header.len()));
+        let column = |name: &str| header.iter().position(|h| *h == name).unwrap();
+        assert_eq!(column("error"), column("error_kind") + 1);
+        assert_eq!(column("fee_lamports"), column("tx_bytes") + 1);
+        let tx = &rows[1];
+        assert_eq!((tx[column("record")], tx[column("landed")], tx[column("outcome")]), ("tx", "false", "failed"));
+    }
+}
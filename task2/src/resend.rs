@@ -0,0 +1,116 @@
+use std::{error::Error, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio::sync::watch;
+
+/// Как часто обновлять высоту блоков: примерно раз в слот.
+const BLOCK_HEIGHT_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Секция `resend` конфига.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ResendConfig {
+    /// Повторять отправку каждые `interval_ms`, пока транзакция не подтвердится или не
+    /// истечёт её блокхэш; без поля транзакция отправляется один раз.
+    pub interval_ms: Option<u64>,
+    /// Общий лимит ожидания целевого уровня подтверждения.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    90_000
+}
+
+impl Default for ResendConfig {
+    fn default() -> Self {
+        Self { interval_ms: None, timeout_ms: default_timeout_ms() }
+    }
+}
+
+impl ResendConfig {
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval_ms.map(Duration::from_millis)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Текущая высота блоков, которую фоновая задача обновляет раз в `BLOCK_HEIGHT_INTERVAL`.
+#[derive(Clone)]
+pub struct BlockHeights(watch::Receiver<u64>);
+
+impl BlockHeights {
+    pub async fn spawn(client: Arc<RpcClient>) -> Result<Self, Box<dyn Error>> {
+        let height = client
+            .get_block_height()
+            .await
+            .map_err(|e| format!("Не удалось получить высоту блоков: {}", e))?;
+        let (publish, heights) = watch::channel(height);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(BLOCK_HEIGHT_INTERVAL).await;
+                // Ошибки опроса пропускаются: до следующего ответа действует прошлая высота.
+                if let Ok(height) = client.get_block_height().await {
+                    if publish.send(height).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self(heights))
+    }
+
+    /// Высота, которую обновляют вручную через возвращённый `watch::Sender`.
+    #[cfg(test)]
+    pub fn fixed(height: u64) -> (watch::Sender<u64>, Self) {
+        let (publish, heights) = watch::channel(height);
+        (publish, Self(heights))
+    }
+
+    pub fn expiry(&self, last_valid_block_height: u64) -> Expiry {
+        Expiry { last_valid_block_height, heights: self.0.clone() }
+    }
+}
+
+/// Срок жизни блокхэша транзакции: после `last_valid_block_height` она уже не попадёт в блок.
+#[derive(Clone)]
+pub struct Expiry {
+    last_valid_block_height: u64,
+    heights: watch::Receiver<u64>,
+}
+
+impl Expiry {
+    pub fn is_expired(&self) -> bool {
+        *self.heights.borrow() > self.last_valid_block_height
+    }
+
+    /// Завершается, когда блокхэш истёк; если высота больше не обновляется — никогда.
+    pub async fn expired(mut self) {
+        let last_valid = self.last_valid_block_height;
+        if self.heights.wait_for(|height| *height > last_valid).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn expiry_follows_block_height() {
+        let (publish, heights) = watch::channel(100);
+        let expiry = BlockHeights(heights).expiry(150);
+        assert!(!expiry.is_expired());
+
+        let waiting = tokio::spawn(expiry.clone().expired());
+        publish.send(150).unwrap();
+        assert!(!expiry.is_expired());
+        publish.send(151).unwrap();
+        waiting.await.unwrap();
+        assert!(expiry.is_expired());
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{message::VersionedMessage, transaction::VersionedTransaction};
+
+use crate::{error::TxError, TxMetrics};
+
+/// Нужна ли симуляция перед отправкой.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Simulation {
+    Off,
+    /// Симулировать и отправлять только транзакции, которые симуляция пропустила.
+    BeforeSend,
+    /// Только симуляция и оценка комиссии, ничего не отправляется.
+    DryRun,
+}
+
+impl Simulation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Simulation::Off => "off",
+            Simulation::BeforeSend => "before_send",
+            Simulation::DryRun => "dry_run",
+        }
+    }
+}
+
+/// Результат симуляции транзакции.
+#[derive(Debug, Clone, Default)]
+pub struct Estimate {
+    /// Комиссия по `getFeeForMessage`.
+    pub fee: Option<u64>,
+    pub units_consumed: Option<u64>,
+    /// Ошибка выполнения в симуляции и последняя строка логов.
+    pub error: Option<String>,
+}
+
+/// Симулирует транзакцию без проверки подписей и запрашивает её комиссию. Сбой самих
+/// запросов — `TxError::Rpc`; ошибка программы в симуляции возвращается в `Estimate::error`.
+pub async fn estimate(client: &RpcClient, tx: &VersionedTransaction) -> Result<Estimate, TxError> {
+    let config = RpcSimulateTransactionConfig {
+        commitment: Some(client.commitment()),
+        ..Default::default()
+    };
+    let simulated = client
+        .simulate_transaction_with_config(tx, config)
+        .await
+        .map_err(|e| TxError::Rpc(format!("simulateTransaction: {}", e)))?
+        .value;
+    let fee = match &tx.message {
+        VersionedMessage::Legacy(message) => client.get_fee_for_message(message).await,
+        VersionedMessage::V0(message) => client.get_fee_for_message(message).await,
+    }
+    .map_err(|e| TxError::Rpc(format!("getFeeForMessage: {}", e)))?;
+
+    let error = simulated.err.map(|err| {
+        match simulated.logs.as_ref().and_then(|logs| logs.last()) {
+            Some(log) => format!("{} ({})", err, log),
+            None => err.to_string(),
+        }
+    });
+    Ok(Estimate { fee: Some(fee), units_consumed: simulated.units_consumed, error })
+}
+
+/// Сводка симуляций прогона.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Estimates {
+    /// Транзакций с оценкой комиссии.
+    pub simulated: usize,
+    pub fee_total: u64,
+    pub fee_mean: f64,
+    pub units_mean: f64,
+    pub units_max: u64,
+}
+
+impl Estimates {
+    /// `None`, если ни одна транзакция не симулировалась.
+    pub fn from_metrics(metrics: &[TxMetrics]) -> Option<Self> {
+        let fees: Vec<u64> = metrics.iter().filter_map(|m| m.fee).collect();
+        if fees.is_empty() {
+            return None;
+        }
+        let units: Vec<u64> = metrics.iter().filter_map(|m| m.units_consumed).collect();
+        let fee_total = fees.iter().sum();
+        Some(Self {
+            simulated: fees.len(),
+            fee_total,
+            fee_mean: fee_total as f64 / fees.len() as f64,
+            units_mean: if units.is_empty() { 0.0 } else { units.iter().sum::<u64>() as f64 / units.len() as f64 },
+            units_max: units.iter().copied().max().unwrap_or(0),
+        })
+    }
+
+    pub fn print(&self) {
+        println!(
+            "\nСимуляция: {} транзакций, комиссия {} лампортов (в среднем {:.0}), CU в среднем {:.0}, максимум {}",
+            self.simulated, self.fee_total, self.fee_mean, self.units_mean, self.units_max
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulated(fee: Option<u64>, units_consumed: Option<u64>) -> TxMetrics {
+        TxMetrics { fee, units_consumed, ..TxMetrics::new(None) }
+    }
+
+    #[test]
+    fn estimates_skip_transactions_without_simulation() {
+        assert_eq!(Estimates::from_metrics(&[]), None);
+        assert_eq!(Estimates::from_metrics(&[simulated(None, None), simulated(None, Some(150))]), None);
+
+        let metrics = [
+            simulated(Some(5_000), Some(150)),
+            simulated(None, None),
+            simulated(Some(10_000), None),
+            simulated(Some(6_000), Some(450)),
+        ];
+        assert_eq!(
+            Estimates::from_metrics(&metrics),
+            Some(Estimates { simulated: 3, fee_total: 21_000, fee_mean: 7_000.0, units_mean: 300.0, units_max: 450 })
+        );
+
+        let without_units = Estimates::from_metrics(&[simulated(Some(5_000), None)]).unwrap();
+        assert_eq!((without_units.units_mean, without_units.units_max), (0.0, 0));
+    }
+}
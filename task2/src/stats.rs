@@ -0,0 +1,425 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    confirmation::Outcome,
+    simulate::{Estimates, Simulation},
+    TxMetrics,
+};
+
+/// Квантили, которые выводятся для каждой фазы.
+pub const PERCENTILES: [(&str, f64); 5] = [("p50", 50.0), ("p90", 90.0), ("p95", 95.0), ("p99", 99.0), ("p99.9", 99.9)];
+
+/// Число бит под линейные подкорзины внутри каждой степени двойки:
+/// 3 бита дают 8 подкорзин и относительную погрешность не больше 12.5%.
+const SUB_BUCKET_BITS: u32 = 3;
+const HISTOGRAM_BAR_WIDTH: u64 = 40;
+
+/// Статистика одной фазы (сборка, симуляция, отправка, финализация, общее время).
+#[derive(Debug, Clone)]
+pub struct PhaseStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub std_dev: Duration,
+    /// Значения квантилей в порядке `PERCENTILES`.
+    pub percentiles: Vec<(&'static str, Duration)>,
+    pub histogram: Histogram,
+}
+
+impl PhaseStats {
+    /// Возвращает `None`, если выборка пуста.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let count = sorted.len();
+        let mean_secs = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / count as f64;
+        let variance = sorted
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean_secs).powi(2))
+            .sum::<f64>()
+            / count as f64;
+
+        let mut histogram = Histogram::default();
+        for sample in &sorted {
+            histogram.record(*sample);
+        }
+
+        Some(Self {
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean: Duration::from_secs_f64(mean_secs),
+            std_dev: Duration::from_secs_f64(variance.sqrt()),
+            percentiles: PERCENTILES
+                .iter()
+                .map(|&(name, q)| (name, percentile(&sorted, q)))
+                .collect(),
+            histogram,
+        })
+    }
+
+    pub fn print(&self, title: &str) {
+        println!("\n{}:", title);
+        println!("Минимальное время: {:.2?}", self.min);
+        println!("Максимальное время: {:.2?}", self.max);
+        println!("Среднее время: {:.2?}", self.mean);
+        println!("Стандартное отклонение: {:.2?}", self.std_dev);
+        let percentiles: Vec<String> = self
+            .percentiles
+            .iter()
+            .map(|(name, value)| format!("{}={:.2?}", name, value))
+            .collect();
+        println!("Квантили: {}", percentiles.join("  "));
+        println!("Гистограмма:");
+        self.histogram.print();
+    }
+}
+
+/// Квантиль по методу ближайшего ранга; `sorted` должен быть отсортирован и непуст.
+pub fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    let rank = (q * sorted.len() as f64 / 100.0).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Гистограмма в духе HDR: корзины растут по степеням двойки, а каждая
+/// степень делится на `2^SUB_BUCKET_BITS` линейных подкорзин. Значения в микросекундах.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Нижняя граница корзины -> число значений.
+    buckets: BTreeMap<u64, u64>,
+    total: u64,
+}
+
+impl Histogram {
+    fn bucket_width(micros: u64) -> u64 {
+        let magnitude = 63 - micros.max(1).leading_zeros();
+        1 << magnitude.saturating_sub(SUB_BUCKET_BITS)
+    }
+
+    pub fn record(&mut self, value: Duration) {
+        let micros = value.as_micros().min(u64::MAX as u128) as u64;
+        let low = micros - micros % Self::bucket_width(micros);
+        *self.buckets.entry(low).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Непустые корзины: (нижняя граница, верхняя граница не включительно, количество).
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, Duration, u64)> + '_ {
+        self.buckets.iter().map(|(&low, &count)| {
+            let high = low.saturating_add(Self::bucket_width(low));
+            (Duration::from_micros(low), Duration::from_micros(high), count)
+        })
+    }
+
+    pub fn print(&self) {
+        let peak = self.buckets.values().copied().max().unwrap_or(0).max(1);
+        let mut cumulative = 0;
+        for (low, high, count) in self.buckets() {
+            cumulative += count;
+            let bar = "#".repeat((count * HISTOGRAM_BAR_WIDTH).div_ceil(peak) as usize);
+            println!(
+                "  {:>10.2?} .. {:<10.2?} {:>6}  {:>6.2}%  {}",
+                low,
+                high,
+                count,
+                cumulative as f64 / self.total as f64 * 100.0,
+                bar
+            );
+        }
+    }
+}
+
+/// Пропускная способность за весь прогон.
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    pub elapsed: Duration,
+    /// Транзакции, дошедшие до сети.
+    pub submitted: usize,
+    /// Транзакции, достигшие целевого уровня подтверждения.
+    pub landed: usize,
+}
+
+impl Throughput {
+    fn per_second(&self, count: usize) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        count as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn submitted_tps(&self) -> f64 {
+        self.per_second(self.submitted)
+    }
+
+    pub fn landed_tps(&self) -> f64 {
+        self.per_second(self.landed)
+    }
+
+    pub fn print(&self) {
+        println!("\nПропускная способность за {:.2?}:", self.elapsed);
+        println!("Отправлено: {} транзакций, {:.2} TPS", self.submitted, self.submitted_tps());
+        println!("Достигли целевого уровня: {} транзакций, {:.2} TPS", self.landed, self.landed_tps());
+    }
+}
+
+/// Число транзакций с каждым итогом отправки.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutcomeCounts {
+    pub landed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub rejected: usize,
+    pub timed_out: usize,
+    #[serde(default)]
+    pub simulated: usize,
+}
+
+impl OutcomeCounts {
+    pub fn from_metrics(metrics: &[TxMetrics]) -> Self {
+        let mut counts = Self::default();
+        for m in metrics {
+            *counts.get_mut(m.outcome) += 1;
+        }
+        counts
+    }
+
+    pub fn get(&self, outcome: Outcome) -> usize {
+        match outcome {
+            Outcome::Landed => self.landed,
+            Outcome::Failed => self.failed,
+            Outcome::Expired => self.expired,
+            Outcome::Rejected => self.rejected,
+            Outcome::TimedOut => self.timed_out,
+            Outcome::Simulated => self.simulated,
+        }
+    }
+
+    fn get_mut(&mut self, outcome: Outcome) -> &mut usize {
+        match outcome {
+            Outcome::Landed => &mut self.landed,
+            Outcome::Failed => &mut self.failed,
+            Outcome::Expired => &mut self.expired,
+            Outcome::Rejected => &mut self.rejected,
+            Outcome::TimedOut => &mut self.timed_out,
+            Outcome::Simulated => &mut self.simulated,
+        }
+    }
+
+    pub fn print(&self) {
+        let counts: Vec<String> = Outcome::ALL
+            .iter()
+            .map(|outcome| format!("{}={}", outcome.name(), self.get(*outcome)))
+            .collect();
+        println!("\nИтоги транзакций: {}", counts.join("  "));
+    }
+}
+
+/// Сводка по прогону: статистика фаз и пропускная способность.
+#[derive(Debug, Clone)]
+pub struct RunStats {
+    /// Сборка и подпись транзакции.
+    pub build: Option<PhaseStats>,
+    /// Симуляция; `None`, если она не включалась.
+    pub simulate: Option<PhaseStats>,
+    pub send: Option<PhaseStats>,
+    pub processed: Option<PhaseStats>,
+    pub confirmed: Option<PhaseStats>,
+    pub finalized: Option<PhaseStats>,
+    pub total: Option<PhaseStats>,
+    /// Возраст блокхэша из кэша на момент подписи.
+    pub blockhash_age: Option<PhaseStats>,
+    pub throughput: Throughput,
+    pub outcomes: OutcomeCounts,
+    /// Число транзакций по типам ошибок.
+    pub errors: BTreeMap<&'static str, usize>,
+    /// Комиссии и CU по симуляции; `None`, если симуляция не включалась.
+    pub estimates: Option<Estimates>,
+    /// Прогон `--dry-run`: отправки не было, фазы отправки и подтверждения неприменимы.
+    pub dry_run: bool,
+    /// Транзакции, дошедшие до симуляции.
+    pub simulations: usize,
+}
+
+impl RunStats {
+    pub fn from_metrics(metrics: &[TxMetrics], elapsed: Duration, simulation: Simulation) -> Self {
+        // Стадии, которые транзакция не прошла до таймаута, в выборку не попадают, как и
+        // нулевые времена отправки у транзакций, не дошедших до сети.
+        let phase = |f: fn(&TxMetrics) -> Option<Duration>| {
+            PhaseStats::from_samples(&metrics.iter().filter_map(f).collect::<Vec<_>>())
+        };
+        Self {
+            build: phase(|m| m.build_time),
+            simulate: phase(|m| m.simulate_time),
+            send: phase(|m| m.outcome.reached_network().then_some(m.send_time)),
+            processed: phase(|m| m.stages.processed),
+            confirmed: phase(|m| m.stages.confirmed),
+            finalized: phase(|m| m.stages.finalized),
+            total: phase(|m| m.outcome.reached_network().then_some(m.total_time)),
+            blockhash_age: phase(|m| m.blockhash_age),
+            throughput: Throughput {
+                elapsed,
+                submitted: metrics.iter().filter(|m| m.outcome.reached_network()).count(),
+                landed: metrics.iter().filter(|m| m.landed).count(),
+            },
+            outcomes: OutcomeCounts::from_metrics(metrics),
+            errors: metrics.iter().filter_map(|m| m.error.as_ref()).fold(BTreeMap::new(), |mut errors, e| {
+                *errors.entry(e.kind()).or_insert(0) += 1;
+                errors
+            }),
+            estimates: Estimates::from_metrics(metrics),
+            dry_run: simulation == Simulation::DryRun,
+            simulations: metrics.iter().filter(|m| m.simulate_time.is_some()).count(),
+        }
+    }
+
+    /// Доля дошедших до сети транзакций, достигших целевого уровня, в процентах.
+    /// В режиме `--dry-run` — доля успешных симуляций среди проведённых.
+    pub fn success_percentage(&self) -> f64 {
+        let (succeeded, attempted) = if self.dry_run {
+            (self.outcomes.simulated, self.simulations)
+        } else {
+            (self.throughput.landed, self.throughput.submitted)
+        };
+        if attempted == 0 {
+            return 0.0;
+        }
+        succeeded as f64 / attempted as f64 * 100.0
+    }
+
+    pub fn print(&self) {
+        let phases = [
+            ("Статистика времени сборки транзакций", &self.build),
+            ("Статистика времени симуляции", &self.simulate),
+            ("Статистика времени отправки транзакций", &self.send),
+            ("Статистика времени до processed", &self.processed),
+            ("Статистика времени до confirmed", &self.confirmed),
+            ("Статистика времени до finalized", &self.finalized),
+            ("Общая статистика времени транзакций", &self.total),
+            ("Возраст блокхэша из кэша", &self.blockhash_age),
+        ];
+        for (title, phase) in phases {
+            if let Some(phase) = phase {
+                phase.print(title);
+            }
+        }
+        if self.dry_run {
+            println!("\nОтправка и подтверждение: неприменимо в режиме --dry-run");
+        } else {
+            self.throughput.print();
+        }
+        self.outcomes.print();
+        if !self.errors.is_empty() {
+            let errors: Vec<String> = self.errors.iter().map(|(kind, count)| format!("{}={}", kind, count)).collect();
+            println!("Ошибки по типам: {}", errors.join("  "));
+        }
+        if let Some(estimates) = &self.estimates {
+            estimates.print();
+        }
+        if self.dry_run {
+            println!("\nПроцент успешных симуляций: {:.2}%", self.success_percentage());
+        } else {
+            println!("\nПроцент транзакций, достигших целевого уровня: {:.2}%", self.success_percentage());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TxError;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples: Vec<Duration> = (1..=1000).rev().map(Duration::from_millis).collect();
+        let stats = PhaseStats::from_samples(&samples).unwrap();
+
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(1000));
+        let values: Vec<u128> = stats.percentiles.iter().map(|(_, d)| d.as_millis()).collect();
+        assert_eq!(values, vec![500, 900, 950, 990, 999]);
+        assert_eq!(stats.std_dev.as_millis(), 288);
+    }
+
+    #[test]
+    fn histogram_buckets_are_log_linear() {
+        let mut histogram = Histogram::default();
+        for micros in [3, 3, 100, 103, 5_000] {
+            histogram.record(Duration::from_micros(micros));
+        }
+        let buckets: Vec<(u128, u128, u64)> = histogram
+            .buckets()
+            .map(|(low, high, count)| (low.as_micros(), high.as_micros(), count))
+            .collect();
+        // 100 и 103 попадают в подкорзину 96..104 октавы 64..128.
+        assert_eq!(buckets, vec![(3, 4, 2), (96, 104, 2), (4_608, 5_120, 1)]);
+    }
+
+    #[test]
+    fn run_errors_are_counted_by_kind() {
+        let with_error = |error: Option<TxError>| TxMetrics { error, ..TxMetrics::new(None) };
+        let metrics = vec![
+            with_error(Some(TxError::Keypair("нет файла".to_string()))),
+            with_error(Some(TxError::Expired)),
+            with_error(None),
+            with_error(Some(TxError::Execution("InsufficientFunds".to_string()))),
+            with_error(Some(TxError::Expired)),
+        ];
+        let stats = RunStats::from_metrics(&metrics, Duration::from_secs(1), Simulation::Off);
+        assert_eq!(stats.errors, BTreeMap::from([("execution", 1), ("expired", 2), ("keypair", 1)]));
+        assert!(RunStats::from_metrics(&metrics[2..3], Duration::from_secs(1), Simulation::Off).errors.is_empty());
+    }
+
+    #[test]
+    fn throughput_counts_only_transactions_that_reached_the_network() {
+        let with_outcome = |outcome: Outcome, landed: bool| TxMetrics {
+            outcome,
+            landed,
+            ..TxMetrics::new(None)
+        };
+        let metrics = vec![
+            with_outcome(Outcome::Landed, true),
+            with_outcome(Outcome::Rejected, false),
+            with_outcome(Outcome::Simulated, false),
+            with_outcome(Outcome::Expired, false),
+            with_outcome(Outcome::Rejected, false),
+        ];
+        let stats = RunStats::from_metrics(&metrics, Duration::from_secs(2), Simulation::Off);
+        assert_eq!((stats.throughput.submitted, stats.throughput.landed), (2, 1));
+        assert_eq!(stats.throughput.submitted_tps(), 1.0);
+        assert_eq!(stats.success_percentage(), 50.0);
+        assert_eq!(stats.outcomes.rejected, 2);
+    }
+
+    #[test]
+    fn send_and_total_latencies_skip_rejected_transactions() {
+        let row = |outcome: Outcome, millis: u64| TxMetrics {
+            outcome,
+            send_time: Duration::from_millis(millis),
+            total_time: Duration::from_millis(millis * 10),
+            ..TxMetrics::new(None)
+        };
+        let metrics = vec![
+            row(Outcome::Rejected, 0),
+            row(Outcome::Landed, 100),
+            row(Outcome::Rejected, 0),
+            row(Outcome::Simulated, 0),
+            row(Outcome::Expired, 300),
+            row(Outcome::Landed, 200),
+            row(Outcome::Rejected, 0),
+        ];
+        let stats = RunStats::from_metrics(&metrics, Duration::from_secs(1), Simulation::Off);
+
+        let send = stats.send.unwrap();
+        assert_eq!((send.min, send.max), (Duration::from_millis(100), Duration::from_millis(300)));
+        assert_eq!(send.percentiles[0].1, Duration::from_millis(200));
+        let total = stats.total.unwrap();
+        assert_eq!(total.min, Duration::from_millis(1000));
+        assert_eq!(total.percentiles[0].1, Duration::from_millis(2000));
+        assert!(RunStats::from_metrics(&metrics[..1], Duration::from_secs(1), Simulation::Off).send.is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use std::{error::Error, sync::Arc};
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::{rpc_client::RpcClient, tpu_client::TpuClient},
+    rpc_config::RpcSendTransactionConfig,
+    rpc_custom_error::JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+    rpc_request::RpcError,
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::{cli::SendMode, error::TxError};
+
+type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+/// Отправляет подписанные транзакции выбранным путём.
+pub enum Submitter {
+    /// `sendTransaction` через RPC-узел, который сам пересылает транзакцию лидеру.
+    Rpc(Arc<RpcClient>),
+    /// Напрямую в TPU текущего и ближайших лидеров по QUIC. Расписание лидеров
+    /// клиент берёт по RPC и обновляет по подписке на слоты.
+    Tpu(QuicTpuClient),
+}
+
+impl Submitter {
+    pub async fn new(mode: SendMode, client: Arc<RpcClient>, ws_url: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match mode {
+            SendMode::Rpc => Submitter::Rpc(client),
+            SendMode::Tpu => {
+                let tpu = TpuClient::new("task2", client, ws_url, TpuClientConfig::default())
+                    .await
+                    .map_err(|e| format!("Не удалось создать TPU-клиент: {}", e))?;
+                Submitter::Tpu(tpu)
+            }
+        })
+    }
+
+    /// Отправляет транзакцию; `config` учитывается только при отправке через RPC.
+    pub async fn send(&self, tx: &VersionedTransaction, config: RpcSendTransactionConfig) -> Result<(), TxError> {
+        match self {
+            Submitter::Rpc(client) => client
+                .send_transaction_with_config(tx, config)
+                .await
+                .map(drop)
+                .map_err(send_error),
+            Submitter::Tpu(tpu) => {
+                let wire = bincode::serialize(tx).map_err(|e| TxError::Build(e.to_string()))?;
+                tpu.try_send_wire_transaction(wire).await.map_err(tpu_error)
+            }
+        }
+    }
+}
+
+/// TPU-клиент не симулирует транзакции, поэтому любая его ошибка — ошибка отправки.
+fn tpu_error(error: impl std::fmt::Display) -> TxError {
+    TxError::Send(format!("TPU: {}", error))
+}
+
+/// Отказ на предварительной симуляции отделяется от остальных ошибок отправки.
+fn send_error(error: ClientError) -> TxError {
+    match error.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+            ..
+        }) => TxError::Simulation(error.to_string()),
+        _ => TxError::Send(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    use crate::cli::Cli;
+
+    #[test]
+    fn send_mode_is_parsed_from_the_command_line() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["task2"], args].concat()).map(|cli| cli.send_mode);
+        assert_eq!(parse(&[]).unwrap(), SendMode::Rpc);
+        assert_eq!(parse(&["--send-mode", "tpu"]).unwrap(), SendMode::Tpu);
+        assert_eq!(parse(&["--send-mode", "rpc"]).unwrap(), SendMode::Rpc);
+        assert!(parse(&["--send-mode", "quic"]).is_err());
+    }
+
+    #[test]
+    fn send_errors_are_mapped_per_path() {
+        let rpc_error = |code| {
+            ClientError::from(RpcError::RpcResponseError {
+                code,
+                message: "Transaction simulation failed".to_string(),
+                data: RpcResponseErrorData::Empty,
+            })
+        };
+        let preflight = send_error(rpc_error(JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE));
+        assert_eq!(preflight.kind(), "simulation");
+        assert_eq!(send_error(rpc_error(-32005)).kind(), "send");
+        assert_eq!(send_error(ClientError::from(ClientErrorKind::Custom("connection refused".to_string()))).kind(), "send");
+
+        assert_eq!(tpu_error("leader unreachable"), TxError::Send("TPU: leader unreachable".to_string()));
+    }
+}
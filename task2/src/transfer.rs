@@ -0,0 +1,177 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use blockhash_cache::BlockhashCache;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::VersionedTransaction,
+};
+
+use crate::{
+    confirmation::{Outcome, Tracker},
+    error::TxError,
+    fees::ComputeBudget,
+    message::MessageBuilder,
+    nonce::NonceAccounts,
+    progress::Progress,
+    resend::{BlockHeights, Expiry, ResendConfig},
+    simulate::{self, Simulation},
+    submit::Submitter,
+    TxMetrics,
+};
+
+/// Пара отправитель → получатель из конфига.
+pub struct Transfer {
+    pub from: Arc<Keypair>,
+    pub to: Pubkey,
+}
+
+/// Всё, что нужно задаче для отправки одного перевода и ожидания его подтверждения.
+pub struct Sender {
+    pub client: Arc<RpcClient>,
+    pub submitter: Arc<Submitter>,
+    pub tracker: Arc<Tracker>,
+    pub commitment: CommitmentConfig,
+    pub message: Arc<MessageBuilder>,
+    /// Инструкции бюджета вычислений, добавляемые перед переводом.
+    pub budget: ComputeBudget,
+    pub resend: ResendConfig,
+    pub block_heights: BlockHeights,
+    /// Nonce-аккаунты отправителей, если транзакции подписываются durable nonce.
+    pub nonces: Option<Arc<NonceAccounts>>,
+    /// Общий кэш блокхэша; без него блокхэш запрашивается для каждой транзакции.
+    pub blockhashes: Option<BlockhashCache>,
+    pub simulation: Simulation,
+    /// Счётчики для строки состояния прогона.
+    pub progress: Arc<Progress>,
+}
+
+impl Sender {
+    pub async fn transfer(&self, transfer: &Transfer, lamports: u64) -> TxMetrics {
+        let mut metrics = TxMetrics::new(self.budget.unit_price);
+        if let Err(e) = self.send_and_confirm(transfer, lamports, &mut metrics).await {
+            metrics.error = Some(e);
+        }
+        metrics
+    }
+
+    /// Заполняет `metrics` по мере продвижения транзакции; ошибка — причина, по которой
+    /// она не дошла до целевого уровня. До отправки итог остаётся `Rejected`.
+    async fn send_and_confirm(&self, transfer: &Transfer, lamports: u64, metrics: &mut TxMetrics) -> Result<(), TxError> {
+        // Nonce занят до конца функции: следующая транзакция отправителя ждёт подтверждения этой.
+        let lease = match &self.nonces {
+            Some(nonces) => Some(
+                nonces.acquire(&self.client, &transfer.from.pubkey()).await.map_err(TxError::Nonce)?,
+            ),
+            None => None,
+        };
+        let instructions = self.budget.instructions();
+        let (built, last_valid_block_height) = match &lease {
+            // Транзакция с durable nonce не истекает по высоте блоков.
+            Some(lease) => {
+                let start_build = Instant::now();
+                let built = lease.build(&self.message, &transfer.from, transfer.to, lamports, instructions);
+                metrics.build_time = Some(start_build.elapsed());
+                (built, u64::MAX)
+            }
+            None => {
+                let (blockhash, last_valid_block_height) = match &self.blockhashes {
+                    Some(cache) => {
+                        let cached = cache.get();
+                        metrics.blockhash_age = Some(cached.age());
+                        (cached.blockhash, cached.last_valid_block_height)
+                    }
+                    None => self.client
+                        .get_latest_blockhash_with_commitment(self.client.commitment()).await
+                        .map_err(|e| TxError::Blockhash(e.to_string()))?,
+                };
+                let start_build = Instant::now();
+                let built = self.message.build(&transfer.from, transfer.to, lamports, instructions, blockhash);
+                metrics.build_time = Some(start_build.elapsed());
+                (built, last_valid_block_height)
+            }
+        };
+        let tx = built.map_err(TxError::Build)?;
+        metrics.signature = tx.signatures[0].to_string();
+        metrics.tx_bytes = bincode::serialized_size(&tx).unwrap_or_default() as usize;
+
+        if self.simulation != Simulation::Off {
+            let start_simulation = Instant::now();
+            let estimate = simulate::estimate(&self.client, &tx).await;
+            metrics.simulate_time = Some(start_simulation.elapsed());
+            let estimate = estimate?;
+            metrics.fee = estimate.fee;
+            metrics.units_consumed = estimate.units_consumed;
+            if let Some(e) = estimate.error {
+                return Err(TxError::Simulation(e));
+            }
+            if self.simulation == Simulation::DryRun {
+                metrics.outcome = Outcome::Simulated;
+                return Ok(());
+            }
+        }
+
+        let expiry = self.block_heights.expiry(last_valid_block_height);
+        let watch = self.tracker
+            .watch(tx.signatures[0], self.commitment, self.resend.timeout(), expiry.clone())
+            .await;
+
+        // При повторной отправке ретраи RPC-узла отключаются: повторами управляет бенчмарк.
+        let config = RpcSendTransactionConfig {
+            preflight_commitment: Some(self.client.commitment().commitment),
+            max_retries: self.resend.interval().map(|_| 0),
+            ..Default::default()
+        };
+        let start_send = Instant::now();
+        let sent = self.submitter.send(&tx, config).await;
+        metrics.send_time = start_send.elapsed();
+        metrics.total_time = metrics.send_time;
+        sent?;
+        self.progress.sent();
+
+        let sent_at = Instant::now();
+        metrics.sent_at = Some(sent_at);
+        let wait = watch.wait(sent_at);
+        let confirmation = match self.resend.interval() {
+            Some(interval) => tokio::select! {
+                confirmation = wait => confirmation,
+                _ = self.rebroadcast(&tx, interval, &expiry, &mut metrics.resends) => unreachable!(),
+            },
+            None => wait.await,
+        };
+
+        metrics.stages = confirmation.stages;
+        metrics.total_time += confirmation.elapsed;
+        metrics.landed = confirmation.outcome == Outcome::Landed;
+        metrics.outcome = confirmation.outcome;
+        match confirmation.outcome {
+            Outcome::Landed => Ok(()),
+            Outcome::Expired => Err(TxError::Expired),
+            Outcome::TimedOut => Err(TxError::Timeout),
+            _ => Err(TxError::Execution(confirmation.error.unwrap_or_default())),
+        }
+    }
+
+    /// Повторно отправляет транзакцию каждые `interval`, пока действует её блокхэш.
+    /// Сама не завершается: её прерывает окончание ожидания подтверждения. Повторы уже
+    /// попавшей в блок транзакции кластер отбрасывает как дубликаты.
+    async fn rebroadcast(&self, tx: &VersionedTransaction, interval: Duration, expiry: &Expiry, resends: &mut u32) {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        while !expiry.is_expired() {
+            tokio::time::sleep(interval).await;
+            // Ошибка повтора не меняет итог: его определяет статус подписи.
+            let _ = self.submitter.send(tx, config).await;
+            *resends += 1;
+        }
+        std::future::pending::<()>().await;
+    }
+}
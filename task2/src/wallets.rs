@@ -0,0 +1,235 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    message::Message,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+use crate::nonce;
+
+/// Сколько пополнений помещается в одну транзакцию мастер-кошелька.
+const FUND_PER_TX: usize = 20;
+/// Сколько кошельков возвращают средства в одной транзакции: каждый добавляет подпись.
+const SWEEP_PER_TX: usize = 8;
+/// Максимум аккаунтов в одном запросе `getMultipleAccounts`.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Имя файла `index`-го кошелька в каталоге.
+fn wallet_file(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("wallet-{:03}.json", index))
+}
+
+/// Кошельки каталога в порядке номеров.
+pub fn load(dir: &Path) -> Result<Vec<Keypair>, Box<dyn Error>> {
+    let mut paths: Vec<(usize, PathBuf)> = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let index = name.strip_prefix("wallet-")?.strip_suffix(".json")?.parse().ok()?;
+            Some((index, path))
+        })
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|(_, path)| read_keypair_file(path).map_err(|e| format!("{}: {}", path.display(), e).into()))
+        .collect()
+}
+
+/// Дополняет каталог новыми кошельками до `count`; уже созданные не перезаписываются.
+fn generate(dir: &Path, count: usize) -> Result<Vec<Keypair>, Box<dyn Error>> {
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut created = 0;
+    for index in 0..count {
+        let path = wallet_file(dir, index);
+        if !path.exists() {
+            write_keypair_file(&Keypair::new(), &path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            created += 1;
+        }
+    }
+    println!("Кошельков в {}: {} (создано {})", dir.display(), count, created);
+    let mut wallets = load(dir)?;
+    wallets.truncate(count);
+    Ok(wallets)
+}
+
+async fn balances(client: &RpcClient, pubkeys: &[Pubkey]) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut balances = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        let accounts = client.get_multiple_accounts(chunk).await?;
+        balances.extend(accounts.iter().map(|account| account.as_ref().map_or(0, |a| a.lamports)));
+    }
+    Ok(balances)
+}
+
+/// Пополнения до `target` для кошельков, у которых на балансе меньше.
+fn top_ups(pubkeys: &[Pubkey], balances: &[u64], target: u64) -> Vec<(Pubkey, u64)> {
+    pubkeys
+        .iter()
+        .zip(balances)
+        .filter(|(_, balance)| **balance < target)
+        .map(|(pubkey, balance)| (*pubkey, target - balance))
+        .collect()
+}
+
+/// Откуда берутся лампорты для кошельков.
+pub enum Funding {
+    /// Переводы с мастер-кошелька пачками по `FUND_PER_TX`.
+    Master(Box<Keypair>),
+    /// `requestAirdrop` на каждый кошелёк (localnet и devnet).
+    Airdrop,
+}
+
+/// Создаёт `count` кошельков в `dir` и пополняет каждый до `lamports`.
+pub async fn setup(
+    client: &RpcClient,
+    dir: &Path,
+    count: usize,
+    lamports: u64,
+    funding: Funding,
+) -> Result<(), Box<dyn Error>> {
+    let rent_exempt = client.get_minimum_balance_for_rent_exemption(0).await?;
+    if lamports < rent_exempt {
+        return Err(format!("баланс кошелька {} меньше минимума для аренды {}", lamports, rent_exempt).into());
+    }
+
+    let wallets = generate(dir, count)?;
+    let pubkeys: Vec<Pubkey> = wallets.iter().map(Keypair::pubkey).collect();
+    let top_ups = top_ups(&pubkeys, &balances(client, &pubkeys).await?, lamports);
+    if top_ups.is_empty() {
+        println!("Все кошельки уже пополнены до {} лампортов", lamports);
+        return Ok(());
+    }
+
+    match funding {
+        Funding::Master(master) => {
+            let needed: u64 = top_ups.iter().map(|(_, amount)| amount).sum();
+            let available = client.get_balance(&master.pubkey()).await?;
+            if available < needed {
+                return Err(format!(
+                    "на мастер-кошельке {} {} лампортов, нужно {} плюс комиссии",
+                    master.pubkey(),
+                    available,
+                    needed
+                )
+                .into());
+            }
+            for batch in top_ups.chunks(FUND_PER_TX) {
+                let instructions: Vec<_> = batch
+                    .iter()
+                    .map(|(to, amount)| system_instruction::transfer(&master.pubkey(), to, *amount))
+                    .collect();
+                let blockhash = client.get_latest_blockhash().await?;
+                let tx = Transaction::new_signed_with_payer(&instructions, Some(&master.pubkey()), &[&master], blockhash);
+                let signature = client.send_and_confirm_transaction(&tx).await?;
+                println!("Пополнено кошельков: {} ({})", batch.len(), signature);
+            }
+        }
+        Funding::Airdrop => {
+            for (to, amount) in &top_ups {
+                let signature = client.request_airdrop(to, *amount).await?;
+                client.poll_for_signature(&signature).await?;
+                println!("Airdrop {} лампортов на {} ({})", amount, to, signature);
+            }
+        }
+    }
+    println!("Готово: пополнено {} из {} кошельков до {} лампортов", top_ups.len(), count, lamports);
+    Ok(())
+}
+
+/// Индекс кошелька, который платит комиссию пачки: самый богатый.
+fn fee_payer(balances: &[u64]) -> usize {
+    balances.iter().enumerate().max_by_key(|(_, balance)| **balance).map_or(0, |(index, _)| index)
+}
+
+/// Суммы перевода каждого кошелька пачки: плательщик переводит остаток за вычетом комиссии.
+/// `None`, если даже самому богатому кошельку её не покрыть.
+fn sweep_amounts(balances: &[u64], fee: u64) -> Option<Vec<u64>> {
+    let payer = fee_payer(balances);
+    if balances[payer] <= fee {
+        return None;
+    }
+    let mut amounts = balances.to_vec();
+    amounts[payer] -= fee;
+    Some(amounts)
+}
+
+/// Закрывает nonce-аккаунты кошельков и переводит весь остаток кошельков каталога на `to`.
+/// Комиссию каждой пачки платит её самый богатый кошелёк; пачки, где его остатка на неё
+/// не хватает, пропускаются и перечисляются в конце.
+pub async fn teardown(client: &RpcClient, dir: &Path, to: &Pubkey) -> Result<(), Box<dyn Error>> {
+    let wallets = load(dir)?;
+    nonce::withdraw_accounts(client, &wallets.iter().collect::<Vec<_>>()).await?;
+    let pubkeys: Vec<Pubkey> = wallets.iter().map(Keypair::pubkey).collect();
+    let balances = balances(client, &pubkeys).await?;
+    let funded: Vec<(&Keypair, u64)> = wallets
+        .iter()
+        .zip(balances)
+        .filter(|(_, balance)| *balance > 0)
+        .collect();
+
+    let mut swept = 0;
+    let mut skipped = Vec::new();
+    for batch in funded.chunks(SWEEP_PER_TX) {
+        let balances: Vec<u64> = batch.iter().map(|(_, balance)| *balance).collect();
+        let transfers = |amounts: &[u64]| -> Vec<_> {
+            batch
+                .iter()
+                .zip(amounts)
+                .map(|((wallet, _), amount)| system_instruction::transfer(&wallet.pubkey(), to, *amount))
+                .collect()
+        };
+        let blockhash = client.get_latest_blockhash().await?;
+        let payer = batch[fee_payer(&balances)].0.pubkey();
+        let fee = client
+            .get_fee_for_message(&Message::new_with_blockhash(&transfers(&balances), Some(&payer), &blockhash))
+            .await?;
+        let Some(amounts) = sweep_amounts(&balances, fee) else {
+            eprintln!("Пропуск пачки из {} кошельков: ни у одного остатка не хватает на комиссию {}", batch.len(), fee);
+            skipped.extend(batch.iter().map(|(wallet, balance)| (wallet.pubkey(), *balance)));
+            continue;
+        };
+        let signers: Vec<&Keypair> = batch.iter().map(|(wallet, _)| *wallet).collect();
+        let tx = Transaction::new_signed_with_payer(&transfers(&amounts), Some(&payer), &signers, blockhash);
+        let signature = client.send_and_confirm_transaction(&tx).await?;
+        let amount: u64 = amounts.iter().sum();
+        swept += amount;
+        println!("Возвращено {} лампортов с {} кошельков ({})", amount, batch.len(), signature);
+    }
+    if !skipped.is_empty() {
+        eprintln!("Остались на кошельках ({}):", skipped.len());
+        for (wallet, balance) in &skipped {
+            eprintln!("  {} — {} лампортов", wallet, balance);
+        }
+    }
+    println!("Готово: на {} возвращено {} лампортов", to, swept);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_ups_fill_only_underfunded_wallets() {
+        let pubkeys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let top_ups = top_ups(&pubkeys, &[0, 1_500, 700], 1_000);
+        assert_eq!(top_ups, vec![(pubkeys[0], 1_000), (pubkeys[2], 300)]);
+    }
+
+    #[test]
+    fn sweep_fee_is_paid_by_the_richest_wallet() {
+        assert_eq!(sweep_amounts(&[3_000, 90_000, 5_000], 5_000), Some(vec![3_000, 85_000, 5_000]));
+        assert_eq!(sweep_amounts(&[4_000, 5_000], 5_000), None);
+        assert_eq!(sweep_amounts(&[5_001], 5_000), Some(vec![1]));
+    }
+}
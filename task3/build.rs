@@ -1,4 +1,10 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/geyser.proto")?;
+    tonic_build::configure()
+        // Обновление с транзакцией намного больше остальных вариантов UpdateOneof.
+        .boxed(".geyser.SubscribeUpdate.update_oneof.transaction")
+        // Сообщения хранилища нужны только как поля типов Geyser, эти два сами не используются.
+        .message_attribute(".solana.storage.ConfirmedBlock.ConfirmedBlock", "#[allow(dead_code)]")
+        .message_attribute(".solana.storage.ConfirmedBlock.ConfirmedTransaction", "#[allow(dead_code)]")
+        .compile_protos(&["proto/geyser.proto"], &["proto"])?;
     Ok(())
 }
@@ -32,8 +32,13 @@ use tonic::metadata::{MetadataKey, MetadataValue};
 
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::time::Duration;
+use tokio::sync::{watch, Semaphore};
 use config::Config;
+use blockhash_cache::{BlockhashCache, Refresh};
+
+/// Блоки приходят чаще, чем меняется ответ getLatestBlockhash, поэтому запросы реже.
+const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_millis(400);
 
 #[derive(Clone)]
 struct AuthInterceptor {
@@ -100,24 +105,45 @@ async fn main() -> anyhow::Result<()> {
     let solana_rpc_client = Arc::new(solana_client::nonblocking::rpc_client::RpcClient::new(
         config.rpc_endpoint.clone()
     ));
+    // Блокхэш обновляется по слотам из того же потока Geyser и общий для всех переводов.
+    let (slots, slot_updates) = watch::channel(0);
+    let refresh = Refresh::Slots { slots: slot_updates, min_interval: BLOCKHASH_REFRESH_INTERVAL };
+    let blockhashes = BlockhashCache::spawn(Arc::clone(&solana_rpc_client), refresh).await?;
+    let transfers = Arc::new(solana::Transfers::default());
     let shared_config = Arc::new(config);
 
     while let Some(update) = response_stream.message().await? {
         if let Some(geyser::subscribe_update::UpdateOneof::Block(block)) = update.update_oneof {
             println!("New block received, slot: {}", block.slot);
+            slots.send_replace(block.slot);
 
             let config = Arc::clone(&shared_config);
             let permit = Arc::clone(&semaphore).acquire_owned().await?;
             let connection = Arc::clone(&solana_rpc_client);
+            let blockhashes = blockhashes.clone();
+            let transfers = Arc::clone(&transfers);
             tokio::spawn(async move {
                 solana::send_sol_transfer(
                     connection,
+                    &blockhashes,
+                    &transfers,
                     &config.recipient_address,
                     config.transfer_amount_sol,
                 )
                 .map(move |result| {
                     match result {
-                        Ok(_) => println!("SOL transfer successful for slot {}", block.slot),
+                        Ok(solana::SignedWith::Cache(cached)) if cached.failed_refreshes > 0 => println!(
+                            "SOL transfer successful for slot {} (blockhash age {:.2?}, stale for {:.2?} after {} failed refreshes)",
+                            block.slot, cached.age(), cached.staleness(), cached.failed_refreshes
+                        ),
+                        Ok(solana::SignedWith::Cache(cached)) => println!(
+                            "SOL transfer successful for slot {} (blockhash age {:.2?})",
+                            block.slot, cached.age()
+                        ),
+                        Ok(solana::SignedWith::Fresh) => println!(
+                            "SOL transfer successful for slot {} (cached blockhash already used, fetched a fresh one)",
+                            block.slot
+                        ),
                         Err(e) => eprintln!("Failed SOL transfer for slot {}: {:?}", block.slot, e),
                     }
                     drop(permit);
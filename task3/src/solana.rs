@@ -1,12 +1,20 @@
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    hash::Hash,
     signer::{keypair::Keypair, Signer},
     system_instruction,
     transaction::Transaction,
     pubkey::Pubkey,
 };
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 use anyhow::Result;
+use blockhash_cache::{BlockhashCache, CachedBlockhash};
 
 pub mod storage {
     pub mod confirmed_block {
@@ -14,11 +22,39 @@ pub mod storage {
     }
 }
 
+/// Общее для всех переводов состояние: без него переводы с одинаковыми отправителем,
+/// получателем и суммой под одним блокхэшем дали бы одинаковые транзакции.
+#[derive(Default)]
+pub struct Transfers {
+    /// Порядковый номер перевода, добавляется к сумме в лампортах.
+    sent: AtomicU64,
+    /// Блокхэш, которым подписан предыдущий перевод.
+    last_blockhash: Mutex<Hash>,
+}
+
+impl Transfers {
+    /// Запоминает блокхэш для подписи; `false`, если им уже подписан предыдущий перевод.
+    fn claim(&self, blockhash: Hash) -> bool {
+        let mut last = self.last_blockhash.lock().unwrap();
+        std::mem::replace(&mut *last, blockhash) != blockhash
+    }
+}
+
+/// Каким блокхэшем подписан перевод.
+pub enum SignedWith {
+    /// Блокхэш из кэша, чтобы вызывающий видел его возраст.
+    Cache(CachedBlockhash),
+    /// Блокхэш из кэша уже использовался, и перевод подписан свежим из RPC.
+    Fresh,
+}
+
 pub async fn send_sol_transfer(
     rpc_client: Arc<RpcClient>,
+    blockhashes: &BlockhashCache,
+    transfers: &Transfers,
     recipient: &str,
     amount_sol: f64,
-) -> Result<()> {
+) -> Result<SignedWith> {
     let sender_keypair = Keypair::from_bytes(&[
         245, 16, 4, 124, 237, 134, 72, 220, 123, 111, 12, 122, 59, 100, 150, 134, 192,
         139, 154, 10, 65, 247, 116, 72, 185, 90, 103, 172, 54, 190, 29, 92, 58, 31,
@@ -27,20 +63,30 @@ pub async fn send_sol_transfer(
     ])?;
 
     let recipient_pubkey = Pubkey::from_str(recipient)?;
-    let lamports = (amount_sol * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+    // Надбавка в лампортах делает каждый перевод уникальным, даже если блокхэш совпал.
+    let lamports = (amount_sol * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64
+        + transfers.sent.fetch_add(1, Ordering::Relaxed);
 
-    let recent_blockhash = rpc_client.get_latest_blockhash()
-        .await?;
+    // Кэш обновляется не чаще `min_interval` и держит старый блокхэш при ошибках RPC,
+    // поэтому уже использованный блокхэш заменяется свежим.
+    let cached = blockhashes.get();
+    let (blockhash, signed_with) = if transfers.claim(cached.blockhash) {
+        (cached.blockhash, SignedWith::Cache(cached))
+    } else {
+        let blockhash = rpc_client.get_latest_blockhash().await?;
+        transfers.claim(blockhash);
+        (blockhash, SignedWith::Fresh)
+    };
     let ix = system_instruction::transfer(&sender_keypair.pubkey(), &recipient_pubkey, lamports);
 
     let tx = Transaction::new_signed_with_payer(
         &[ix],
         Some(&sender_keypair.pubkey()),
         &[&sender_keypair],
-        recent_blockhash,
+        blockhash,
     );
 
     rpc_client.send_and_confirm_transaction(&tx).await?;
 
-    Ok(())
+    Ok(signed_with)
 }
\ No newline at end of file